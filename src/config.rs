@@ -0,0 +1,510 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, error::Error, fs, path::PathBuf};
+
+/*
+ * Config
+ * user settings read from $XDG_CONFIG_HOME/ctfterm/config.toml
+ * (or ~/.config/ctfterm/config.toml), every field has a default
+ * so a missing file or missing section is fine
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    // titles of events followed for notifications,
+    // kept ahead of the tables so it serializes as plain toml
+    pub watchlist: Vec<String>,
+    pub pagination: PaginationConfig,
+    pub leaderboard: LeaderboardConfig,
+    pub team: TeamConfig,
+    pub running: RunningConfig,
+    pub time: TimeConfig,
+    pub sources: SourcesConfig,
+    pub theme: ThemeConfig,
+    pub layout: LayoutConfig,
+    pub notifications: NotificationConfig,
+    pub daemon: DaemonConfig,
+    pub http: HttpConfig,
+    pub account: AccountConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaginationConfig {
+    // maximum number of pages fetched per paginated panel
+    pub max_pages: u32,
+    // fetch the next page once the selection is this many rows from the end
+    pub prefetch_rows: usize,
+}
+
+impl Default for PaginationConfig {
+    fn default() -> Self {
+        PaginationConfig {
+            max_pages: 10,
+            prefetch_rows: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LeaderboardView {
+    Global,
+    Country,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LeaderboardConfig {
+    // rating year to show, the current year when unset
+    pub year: Option<i32>,
+    // two letter country code used by the country view, e.g. "US"
+    pub country: String,
+    pub view: LeaderboardView,
+    // flag emoji in front of country names
+    pub flags: bool,
+}
+
+impl Default for LeaderboardConfig {
+    fn default() -> Self {
+        LeaderboardConfig {
+            year: None,
+            country: String::from("US"),
+            view: LeaderboardView::Global,
+            flags: false,
+        }
+    }
+}
+
+// the dashboard panels, in the order 'W' moves focus through them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Panel {
+    Watchlist,
+    Running,
+    // past events, or the upcoming events / charts / comparison in their place
+    Main,
+    Writeups,
+    Leaderboard,
+}
+
+impl Panel {
+    pub const ALL: [Panel; 5] = [Panel::Watchlist, Panel::Running, Panel::Main, Panel::Writeups, Panel::Leaderboard];
+}
+
+/*
+ * LayoutConfig
+ * the dashboard as rows from top to bottom, each row split into columns
+ * and each column stacking panels, sizes are relative to their siblings
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayoutConfig {
+    // panels left out of the dashboard, their space goes to the others
+    pub hidden: Vec<Panel>,
+    // below this many columns or rows only one panel is shown at a time
+    pub compact_width: u16,
+    pub compact_height: u16,
+    pub rows: Vec<RowConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowConfig {
+    pub height: u16,
+    pub columns: Vec<ColumnConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnConfig {
+    pub width: u16,
+    pub panels: Vec<PanelConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PanelConfig {
+    pub panel: Panel,
+    #[serde(default = "full_height")]
+    pub height: u16,
+}
+
+fn full_height() -> u16 {
+    100
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        let column = |width, panels: &[(Panel, u16)]| ColumnConfig {
+            width,
+            panels: panels.iter().map(|&(panel, height)| PanelConfig { panel, height }).collect(),
+        };
+        LayoutConfig {
+            hidden: vec![],
+            compact_width: 100,
+            compact_height: 30,
+            rows: vec![
+                RowConfig {
+                    height: 75,
+                    columns: vec![
+                        column(30, &[(Panel::Running, 20), (Panel::Leaderboard, 80)]),
+                        column(70, &[(Panel::Main, 100)]),
+                    ],
+                },
+                RowConfig {
+                    height: 25,
+                    columns: vec![
+                        column(50, &[(Panel::Writeups, 100)]),
+                        column(50, &[(Panel::Watchlist, 100)]),
+                    ],
+                },
+            ],
+        }
+    }
+}
+
+impl LayoutConfig {
+    // whether the panel is placed somewhere in the rows and not hidden
+    pub fn shows(&self, panel: Panel) -> bool {
+        !self.hidden.contains(&panel)
+            && self.rows.iter()
+                .flat_map(|r| r.columns.iter())
+                .flat_map(|c| c.panels.iter())
+                .any(|p| p.panel == panel)
+    }
+
+    pub fn compact(&self, width: u16, height: u16) -> bool {
+        width < self.compact_width || height < self.compact_height
+    }
+
+    // the panels on screen in focus order
+    pub fn visible(&self) -> Vec<Panel> {
+        Panel::ALL.iter().copied().filter(|p| self.shows(*p)).collect()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TeamConfig {
+    // your ctftime team id, shown in the My Team tab
+    pub id: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DateFormat {
+    // "Apr 08 16:00 CEST"
+    Absolute,
+    // "in 3h 12m", "2d 4h ago"
+    Relative,
+    // "2022-04-08T16:00+02:00"
+    Iso,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeConfig {
+    // "local", "UTC" or an IANA name such as "Europe/Berlin"
+    pub timezone: String,
+    pub format: DateFormat,
+}
+
+impl Default for TimeConfig {
+    fn default() -> Self {
+        TimeConfig {
+            timezone: String::from("local"),
+            format: DateFormat::Absolute,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunningConfig {
+    // show running events as a single scrolling line instead of a list
+    pub ticker: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    // dark, light, high-contrast or monochrome
+    pub name: String,
+    // per color overrides, e.g. title = "#ff8800"
+    pub colors: HashMap<String, String>,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        ThemeConfig {
+            name: String::from("dark"),
+            colors: HashMap::new(),
+        }
+    }
+}
+
+// where a panel's data comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    // scraped ctftime pages
+    Html,
+    // ctftime's json api
+    Api,
+    Rss,
+    // icalendar export
+    Ics,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SourcesConfig {
+    // api, rss or ics
+    pub upcoming: Source,
+    // html or rss
+    pub past_events: Source,
+    // html or rss
+    pub writeups: Source,
+    pub running_rss: String,
+    pub upcoming_rss: String,
+    pub upcoming_ics: String,
+    pub past_events_rss: String,
+    pub writeups_rss: String,
+}
+
+impl Default for SourcesConfig {
+    fn default() -> Self {
+        SourcesConfig {
+            upcoming: Source::Api,
+            past_events: Source::Html,
+            writeups: Source::Html,
+            running_rss: String::from("https://ctftime.org/event/list/running/rss/"),
+            upcoming_rss: String::from("https://ctftime.org/event/list/upcoming/rss/"),
+            upcoming_ics: String::from("https://ctftime.org/event/list/upcoming/ical/"),
+            past_events_rss: String::from("https://ctftime.org/event/list/past/rss/"),
+            writeups_rss: String::from("https://ctftime.org/writeups/rss/"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationConfig {
+    // seconds between background refreshes of the watched feeds and the leaderboard
+    pub refresh_secs: u64,
+    // announce upcoming events this many minutes before they start
+    pub lead_minutes: i64,
+    pub bell: bool,
+    // use notify-send when it is installed
+    pub desktop: bool,
+    pub toast_secs: u64,
+    pub filter: EventFilter,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        NotificationConfig {
+            refresh_secs: 300,
+            lead_minutes: 30,
+            bell: true,
+            desktop: true,
+            toast_secs: 8,
+            filter: EventFilter::default(),
+        }
+    }
+}
+
+/*
+ * EventFilter
+ * upcoming events matching this are announced even when not watched
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventFilter {
+    pub enabled: bool,
+    pub min_weight: f64,
+    // e.g. ["Jeopardy", "Attack-Defense"], empty allows any format
+    pub formats: Vec<String>,
+    pub online_only: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DaemonConfig {
+    // seconds between checks in `ctfterm daemon`
+    pub interval_secs: u64,
+    // ctftime id of our team, rank changes are announced when set
+    pub team_id: Option<u32>,
+    pub webhooks: Vec<Webhook>,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        DaemonConfig {
+            interval_secs: 600,
+            team_id: None,
+            webhooks: vec![],
+        }
+    }
+}
+
+// the ctftime login saved with `ctfterm login`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccountConfig {
+    // keep the credentials in the OS keyring, a file in the data directory otherwise
+    pub keyring: bool,
+}
+
+impl Default for AccountConfig {
+    fn default() -> Self {
+        AccountConfig { keyring: true }
+    }
+}
+
+// the one http client every fetch goes through
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    // where the ctftime pages and api are fetched from, the feeds have their own urls in [sources]
+    pub base_url: String,
+    pub user_agent: String,
+    // whole request, and connecting alone
+    pub timeout_secs: u64,
+    pub connect_timeout_secs: u64,
+    // further attempts after a failed request, waiting backoff_ms, then twice that, ...
+    pub retries: u32,
+    pub backoff_ms: u64,
+    // least time between two requests to ctftime, across all panels
+    pub min_interval_ms: u64,
+    // keep pages with an ETag / Last-Modified and ask ctftime whether they changed
+    pub cache: bool,
+    // "http://host:3128", "https://..." or "socks5://host:1080", else HTTP(S)_PROXY is used
+    pub proxy: Option<String>,
+    // PEM file of extra root certificates, e.g. a corporate proxy's
+    pub ca_bundle: Option<PathBuf>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            base_url: String::from("https://ctftime.org"),
+            user_agent: format!("ctfterm/{}", env!("CARGO_PKG_VERSION")),
+            timeout_secs: 30,
+            connect_timeout_secs: 10,
+            retries: 3,
+            backoff_ms: 500,
+            min_interval_ms: 500,
+            cache: true,
+            proxy: None,
+            ca_bundle: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookKind {
+    Discord,
+    Slack,
+    // matrix-hookshot style generic webhook
+    Matrix,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub kind: WebhookKind,
+    pub url: String,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        if let Ok(path) = std::env::var("CTFTERM_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+        let base = match std::env::var("XDG_CONFIG_HOME") {
+            Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(std::env::var("HOME").ok()?).join(".config"),
+        };
+        Some(base.join("ctfterm").join("config.toml"))
+    }
+
+    pub fn load() -> Result<Config, Box<dyn Error>> {
+        match Config::path() {
+            Some(path) if path.exists() => {
+                let s = fs::read_to_string(&path)?;
+                toml::from_str(&s).map_err(|e| format!("{}: {}", path.display(), e).into())
+            }
+            _ => Ok(Config::default()),
+        }
+    }
+
+    // directory for state kept between runs, $XDG_DATA_HOME/ctfterm or ~/.local/share/ctfterm
+    pub fn data_dir() -> Option<PathBuf> {
+        let base = match std::env::var("XDG_DATA_HOME") {
+            Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(std::env::var("HOME").ok()?).join(".local").join("share"),
+        };
+        Some(base.join("ctfterm"))
+    }
+
+    // directory for data that can be thrown away, $XDG_CACHE_HOME/ctfterm or ~/.cache/ctfterm
+    pub fn cache_dir() -> Option<PathBuf> {
+        let base = match std::env::var("XDG_CACHE_HOME") {
+            Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(std::env::var("HOME").ok()?).join(".cache"),
+        };
+        Some(base.join("ctfterm"))
+    }
+
+}
+
+/*
+ * UiState
+ * selections made in the tui, kept in $XDG_DATA_HOME/ctfterm/state.json
+ * so config.toml is only ever read; what is unset keeps the config's value
+ */
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiState {
+    pub year: Option<i32>,
+    pub view: Option<LeaderboardView>,
+    pub ticker: Option<bool>,
+    pub watchlist: Option<Vec<String>>,
+}
+
+impl UiState {
+    fn path() -> Option<PathBuf> {
+        Some(Config::data_dir()?.join("state.json"))
+    }
+
+    pub fn load() -> UiState {
+        UiState::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    // the selections as they are in the config now
+    pub fn of(config: &Config) -> UiState {
+        UiState {
+            year: config.leaderboard.year,
+            view: Some(config.leaderboard.view),
+            ticker: Some(config.running.ticker),
+            watchlist: Some(config.watchlist.clone()),
+        }
+    }
+
+    pub fn apply(self, config: &mut Config) {
+        config.leaderboard.year = self.year.or(config.leaderboard.year);
+        config.leaderboard.view = self.view.unwrap_or(config.leaderboard.view);
+        config.running.ticker = self.ticker.unwrap_or(config.running.ticker);
+        config.watchlist = self.watchlist.unwrap_or_else(|| config.watchlist.clone());
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = UiState::path().ok_or("no data directory")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
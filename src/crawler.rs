@@ -1,56 +1,64 @@
-use error_chain::error_chain;
+use crate::countries;
+use crate::http;
+use error_chain::{bail, error_chain};
 use quick_xml::Reader;
 use quick_xml::events::Event;
 use scraper::{Html, Selector};
+use serde::Deserialize;
+use std::collections::HashMap;
 
 error_chain! {
       foreign_links {
           ReqError(reqwest::Error);
           IoError(std::io::Error);
+          JsonError(serde_json::Error);
+          XmlError(quick_xml::Error);
       }
 }
 
-pub async fn get_past_events() -> Result<Vec<Vec<String>>> {
-	let mut resp = reqwest::get("https://ctftime.org/event/list/past").await.unwrap(); 
-	let body = resp.text().await.unwrap();
+// pull the numeric id out of a ctftime link such as "/team/1234"
+fn link_id(href: &str, prefix: &str) -> Option<u32> {
+    href.strip_prefix(prefix)?.trim_matches('/').split('/').next()?.parse().ok()
+}
+
+// event id from a full or relative ctftime event url
+pub fn event_id(url: &str) -> Option<u32> {
+    let path = url.find("/event/").map(|i| &url[i..])?;
+    link_id(path, "/event/")
+}
+
+/*
+ * get_past_events
+ * the cells of every past event row and the event id from its link,
+ * the date text is left as ctftime wrote it for the loader to parse
+ */
+pub async fn get_past_events(page: u32) -> Result<Vec<(Vec<String>, Option<u32>)>> {
+	let url = http::url(&format!("/event/list/past?page={}", page));
+	let body = http::text(url).await?;
 	let fragment = Html::parse_document(&body);
     let mut ret = vec![];
 
 	let tr_selector = Selector::parse("tr").unwrap();
 	let td_selector = Selector::parse("td").unwrap();
+	let event_selector = Selector::parse("a[href^='/event/']").unwrap();
 
 	for tr_element in fragment.select(&tr_selector) {
-        let mut i:u8 = 0;
-        let mut new_event = vec![];
-        for td_element in tr_element.select(&td_selector) {
-            if i >= 2 {
-                break;
-            }
-            else
-            {
-                let mut s: String = String::from( td_element.text().collect::<Vec<_>>()[0]);
-                // parse date text to fit more easily
-                if i > 0 {
-                    let comma_idx1: usize = s.match_indices(",").map(|(i, _)|i).collect::<Vec<usize>>()[0];
-                    let dash_idx: usize = s.match_indices(" — ").map(|(i, _)|i).collect::<Vec<usize>>()[0];
-                    s.replace_range((comma_idx1..dash_idx), "");
-
-                    let comma_idx2: usize = s.match_indices(",").map(|(i, _)|i).collect::<Vec<usize>>()[0];
-                    let end_idx: usize = comma_idx2 + 11; 
-                    s.replace_range((comma_idx2..end_idx), "");
-                }
-                new_event.push(s);
-            }
-            i+=1;
+        let new_event: Vec<String> = tr_element.select(&td_selector)
+            .map(|td| td.text().collect::<String>().trim().to_string())
+            .collect();
+        let event_id = tr_element.select(&event_selector)
+            .find_map(|a| link_id(a.value().attr("href")?, "/event/"));
+        if !new_event.is_empty()
+        {
+            ret.push((new_event, event_id));
         }
-       ret.push(new_event);
 	}
-   Ok(ret) 
+   Ok(ret)
 }
 
-pub async fn get_writeups() -> Result<Vec<Vec<String>>> {
-	let mut resp = reqwest::get("https://ctftime.org/writeups").await.unwrap(); 
-	let body = resp.text().await.unwrap();
+pub async fn get_writeups(page: u32) -> Result<Vec<Vec<String>>> {
+	let url = http::url(&format!("/writeups?page={}", page));
+	let body = http::text(url).await?;
 	let fragment = Html::parse_document(&body);
     let mut ret = vec![];
 
@@ -61,12 +69,11 @@ pub async fn get_writeups() -> Result<Vec<Vec<String>>> {
 
 	for tr_element in fragment.select(&tr_selector) {
         let mut new_event = vec![];
-        let mut curr_idx = 0;
-        for td_element in tr_element.select(&td_selector) {
+        for (curr_idx, td_element) in tr_element.select(&td_selector).enumerate() {
             // parse new td element
             let elem = td_element.text().collect::<Vec<_>>();
             // ensure elem has an index to be accessed
-            if elem.len() > 0
+            if !elem.is_empty()
             {
                 // check for tags data
                 if curr_idx == tags_idx
@@ -75,14 +82,14 @@ pub async fn get_writeups() -> Result<Vec<Vec<String>>> {
                     let mut subindex = 0;
                     while subindex < elem.len()
                     {
-                        let new_str = &String::from(elem[subindex]);
-                        if new_str.contains("\n\n") == false
+                        let new_str = elem[subindex];
+                        if !new_str.contains("\n\n")
                         {
                             s.push_str(new_str);
                         }
                         else
                         {
-                            s.push_str(&" ".to_string());
+                            s.push(' ');
                         }
                         subindex += 1;
                     }
@@ -91,16 +98,13 @@ pub async fn get_writeups() -> Result<Vec<Vec<String>>> {
                 else
                 {
                     // grab current data
-                    let mut s: String = String::from( elem[0] );
+                    let s: String = String::from( elem[0] );
                     new_event.push(s);
                 }
             }
-            // increase current idx
-            // useful for knowing which data we are dealing with
-            curr_idx += 1;
         }
         // append new writeup if it's not empty
-        if new_event.len() > 0
+        if !new_event.is_empty()
         {
             ret.push(new_event);
         }
@@ -108,71 +112,646 @@ pub async fn get_writeups() -> Result<Vec<Vec<String>>> {
    Ok(ret) 
 }
 
-pub async fn get_stats() -> Result<Vec<Vec<String>>> {
-	let mut resp = reqwest::get("https://ctftime.org/stats/").await.unwrap(); 
-	let body = resp.text().await.unwrap();
-	let fragment = Html::parse_document(&body);
+// one row of the rating table
+pub struct StatRow {
+    pub cells: Vec<String>,
+    // from the team link
+    pub team_id: Option<u32>,
+    // two letter code from the flag link or image, the flag cell itself has no text
+    pub country: Option<String>,
+}
+
+// "US" from a /stats/2022/US link or a /static/images/f/us.png flag
+fn flag_code(href: Option<&str>, src: Option<&str>) -> Option<String> {
+    let from_link = href.and_then(|h| h.strip_prefix("/stats/")?.trim_matches('/').split('/').nth(1));
+    let from_image = src.and_then(|s| s.rsplit('/').next()?.split('.').next());
+    from_link.or(from_image).and_then(countries::normalize)
+}
+
+/*
+ * get_stats
+ * scrape the rating table for a year, optionally narrowed to one country,
+ * returns the header labels alongside the rows so callers can find columns by name,
+ * each row carries the team id taken from its team link and the country of its flag
+ */
+pub async fn get_stats(year: i32, country: Option<&str>) -> Result<(Vec<String>, Vec<StatRow>)> {
+	let url = match country {
+		Some(code) => http::url(&format!("/stats/{}/{}", year, code)),
+		None => http::url(&format!("/stats/{}", year)),
+	};
+	let body = http::text(url).await?;
+    Ok(team_rows(&body))
+}
+
+/*
+ * get_scoreboard
+ * the final scoreboard on an event page, only the rows linking a team,
+ * with the header labels to find the points column by
+ */
+pub async fn get_scoreboard(id: u32) -> Result<(Vec<String>, Vec<StatRow>)> {
+    let url = http::url(&format!("/event/{}", id));
+    let body = http::text(url).await?;
+    let (headers, rows) = team_rows(&body);
+    Ok((headers, rows.into_iter().filter(|r| r.team_id.is_some()).collect()))
+}
+
+// header labels of the first table and every row of a page listing teams
+fn team_rows(body: &str) -> (Vec<String>, Vec<StatRow>) {
+	let fragment = Html::parse_document(body);
+    let mut headers = vec![];
     let mut ret = vec![];
 
 	let tr_selector = Selector::parse("tr").unwrap();
+	let th_selector = Selector::parse("th").unwrap();
 	let td_selector = Selector::parse("td").unwrap();
+	let team_selector = Selector::parse("a[href^='/team/']").unwrap();
+	let flag_link_selector = Selector::parse("a[href^='/stats/']").unwrap();
+	let flag_image_selector = Selector::parse("img").unwrap();
 
 	for tr_element in fragment.select(&tr_selector) {
-        let mut new_event = vec![];
-        for td_element in tr_element.select(&td_selector) {
-            // parse new td element
-            let elem = td_element.text().collect::<Vec<_>>();
-            // ensure elem has an index to be accessed
-            if elem.len() > 0
-            {
-                let mut s: String = String::from( elem[0] );
-                new_event.push(s);
-            }
+        if headers.is_empty()
+        {
+            headers = tr_element.select(&th_selector)
+                .map(|th| th.text().collect::<String>().trim().to_string())
+                .collect();
         }
+
+        // keep empty cells (e.g. flag images) so columns line up with the headers
+        let new_stat: Vec<String> = tr_element.select(&td_selector)
+            .map(|td| td.text().collect::<String>().trim().to_string())
+            .collect();
+        let team_id = tr_element.select(&team_selector)
+            .find_map(|a| link_id(a.value().attr("href")?, "/team/"));
+        let country = tr_element.select(&flag_link_selector)
+            .find_map(|a| flag_code(a.value().attr("href"), None))
+            .or_else(|| tr_element.select(&flag_image_selector)
+                .filter(|img| img.value().attr("src").is_some_and(|s| s.contains("/f/")))
+                .find_map(|img| flag_code(None, img.value().attr("src"))));
         // append new leaderboard stat if it's not empty
-        if new_event.len() > 0
+        if !new_stat.is_empty()
         {
-            ret.push(new_event);
+            ret.push(StatRow { cells: new_stat, team_id, country });
         }
 	}
-   Ok(ret) 
+    (headers, ret)
 }
 
-pub async fn crawl() -> Result<Vec<String>> {
-  let res = reqwest::get("https://ctftime.org/event/list/running/rss/")
-    .await?
-    .text()
-    .await?;
+#[derive(Debug, Deserialize)]
+pub struct TeamYearRating {
+    pub rating_place: Option<u32>,
+    pub rating_points: Option<f64>,
+    pub country_place: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TeamInfo {
+    pub id: u32,
+    pub name: String,
+    #[serde(default)]
+    pub country: String,
+    #[serde(default)]
+    pub academic: bool,
+    #[serde(default)]
+    pub rating: HashMap<String, TeamYearRating>,
+}
 
-    let mut reader = Reader::from_str(res.as_str());
+/*
+ * get_team
+ * team details and per-year rating from the ctftime json api
+ */
+pub async fn get_team(id: u32) -> Result<TeamInfo> {
+    let url = http::url(&format!("/api/v1/teams/{}/", id));
+    let body = http::text(url).await?;
+    Ok(serde_json::from_str(&body)?)
+}
+
+/*
+ * get_team_results
+ * scrape every yearly rating table on a team page,
+ * rows are (place, event, ctf points, rating points) tagged with the year and event id
+ */
+pub async fn get_team_results(id: u32) -> Result<Vec<(i32, Vec<String>, Option<u32>)>> {
+    let url = http::url(&format!("/team/{}", id));
+    let body = http::text(url).await?;
+	let fragment = Html::parse_document(&body);
+    let mut ret = vec![];
+
+	let tab_selector = Selector::parse("div[id^='rating_']").unwrap();
+	let tr_selector = Selector::parse("tr").unwrap();
+	let td_selector = Selector::parse("td").unwrap();
+	let event_selector = Selector::parse("a[href^='/event/']").unwrap();
+
+	for tab_element in fragment.select(&tab_selector) {
+        let year = match tab_element.value().id().and_then(|id| id.strip_prefix("rating_")?.parse().ok()) {
+            Some(year) => year,
+            None => continue,
+        };
+        for tr_element in tab_element.select(&tr_selector) {
+            let cells: Vec<String> = tr_element.select(&td_selector)
+                .map(|td| td.text().collect::<String>().trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+            let event_id = tr_element.select(&event_selector)
+                .find_map(|a| link_id(a.value().attr("href")?, "/event/"));
+            if cells.len() >= 4
+            {
+                ret.push((year, cells, event_id));
+            }
+        }
+	}
+   Ok(ret)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApiEvent {
+    pub id: u32,
+    pub title: String,
+    pub start: String,
+    pub finish: String,
+    #[serde(default)]
+    pub format: String,
+    #[serde(default)]
+    pub weight: f64,
+    #[serde(default)]
+    pub onsite: bool,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub location: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub participants: u32,
+    #[serde(default)]
+    pub organizers: Vec<ApiOrganizer>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApiOrganizer {
+    pub name: String,
+}
+
+/*
+ * get_event
+ * one event with its description from the ctftime json api
+ */
+pub async fn get_event(id: u32) -> Result<ApiEvent> {
+    let url = http::url(&format!("/api/v1/events/{}/", id));
+    let body = http::text(url).await?;
+    Ok(serde_json::from_str(&body)?)
+}
+
+/*
+ * login_form
+ * where the login form posts to and the csrf token ctftime puts into it;
+ * the form is the one with a password field, the search form carries a token too
+ */
+fn login_form(body: &str) -> Option<(String, String)> {
+	let fragment = Html::parse_document(body);
+	let form_selector = Selector::parse("form").unwrap();
+	let password_selector = Selector::parse("input[type='password']").unwrap();
+	let token_selector = Selector::parse("input[name='csrfmiddlewaretoken']").unwrap();
+    let form = fragment.select(&form_selector).find(|form| form.select(&password_selector).next().is_some())?;
+    let token = form.select(&token_selector).find_map(|input| input.value().attr("value"))?;
+    let action = form.value().attr("action").filter(|a| !a.is_empty()).unwrap_or("/login/");
+    Some((action.to_string(), token.to_string()))
+}
+
+/*
+ * login
+ * post the login form with its csrf token,
+ * the session ends up in the shared client's cookies
+ */
+pub async fn login(username: &str, password: &str) -> Result<()> {
+    let url = http::url("/login/");
+    let body = http::text(&url).await?;
+    let (action, token) = match login_form(&body) {
+        Some(form) => form,
+        None => bail!("no login form on {}", url),
+    };
+    let action = if action.starts_with('/') { http::url(&action) } else { action };
+    http::post_form(&action, &[("csrfmiddlewaretoken", &token), ("username", username), ("password", password)], &url).await?;
+    Ok(())
+}
+
+/*
+ * get_user
+ * name of the logged in user from the link to their profile in the menu,
+ * None when the session is missing or expired
+ */
+pub async fn get_user() -> Result<Option<String>> {
+    let body = http::text(http::url("/")).await?;
+	let fragment = Html::parse_document(&body);
+	let logout_selector = Selector::parse("a[href^='/logout']").unwrap();
+	let user_selector = Selector::parse("a[href^='/user/']").unwrap();
+    if fragment.select(&logout_selector).next().is_none()
+    {
+        return Ok(None);
+    }
+    let name = fragment.select(&user_selector)
+        .map(|a| a.text().collect::<String>().trim().to_string())
+        .find(|name| !name.is_empty());
+    Ok(Some(name.unwrap_or_else(|| String::from("logged in"))))
+}
+
+/*
+ * event_account
+ * (registered, vote open) for our team on an event page, as only shown when logged in:
+ * a registered team gets the form to cancel its participation,
+ * teams that played get the weight voting form until voting closes
+ */
+fn event_account(body: &str) -> Result<(bool, bool)> {
+	let fragment = Html::parse_document(body);
+	let logout_selector = Selector::parse("a[href^='/logout']").unwrap();
+	let cancel_selector = Selector::parse("form[action*='/participate/cancel']").unwrap();
+	let vote_selector = Selector::parse("form[action*='/weight'] select[name='weight']").unwrap();
+    // without the session both panels would be silently empty
+    if fragment.select(&logout_selector).next().is_none()
+    {
+        bail!("not logged in");
+    }
+    let registered = fragment.select(&cancel_selector).next().is_some();
+    let vote_open = fragment.select(&vote_selector).next().is_some();
+    Ok((registered, vote_open))
+}
+
+pub async fn get_event_account(id: u32) -> Result<(bool, bool)> {
+    let url = http::url(&format!("/event/{}", id));
+    let body = http::text(url).await?;
+    event_account(&body)
+}
+
+/*
+ * get_upcoming_events
+ * events starting within the next `days` days from the ctftime json api
+ */
+pub async fn get_upcoming_events(days: i64) -> Result<Vec<ApiEvent>> {
+    let now = chrono::Utc::now().timestamp();
+    let url = http::url(&format!(
+        "/api/v1/events/?limit=100&start={}&finish={}",
+        now, now + days * 24 * 60 * 60
+    ));
+    let body = http::text(url).await?;
+    Ok(serde_json::from_str(&body)?)
+}
+
+/*
+ * RssItem
+ * one <item> of a ctftime event feed with every field the feed provides
+ */
+#[derive(Debug, Default, Clone)]
+pub struct RssItem {
+    pub title: String,
+    pub link: String,
+    pub start_date: String,
+    pub finish_date: String,
+    pub format: String,
+    pub weight: f64,
+    pub ctftime_url: String,
+    pub organizers: Vec<String>,
+    pub logo_url: String,
+    pub guid: String,
+    // every other element of the item by name, e.g. "description" or "pubDate"
+    pub extra: HashMap<String, String>,
+}
+
+impl RssItem {
+    fn set(&mut self, field: &[u8], txt: String) {
+        match field {
+            b"title" => self.title = txt,
+            b"link" => self.link = txt,
+            b"start_date" => self.start_date = txt,
+            b"finish_date" => self.finish_date = txt,
+            b"format_text" => self.format = txt,
+            // the numeric format code only fills in when there is no text
+            b"format" if self.format.is_empty() => self.format = txt,
+            b"weight" => self.weight = txt.trim().parse().unwrap_or(0.0),
+            b"ctftime_url" => self.ctftime_url = txt,
+            b"organizers" => self.organizers = parse_organizers(&txt),
+            b"logo_url" => self.logo_url = txt,
+            b"guid" => self.guid = txt,
+            _ => {
+                self.extra.insert(String::from_utf8_lossy(field).into_owned(), txt);
+            }
+        }
+    }
+}
+
+// organizers come as a json list of {"id", "name"}, fall back to the raw text
+fn parse_organizers(txt: &str) -> Vec<String> {
+    #[derive(Deserialize)]
+    struct Organizer {
+        name: String,
+    }
+    match serde_json::from_str::<Vec<Organizer>>(txt) {
+        Ok(orgs) => orgs.into_iter().map(|o| o.name).collect(),
+        Err(_) if txt.trim().is_empty() => vec![],
+        Err(_) => vec![txt.trim().to_string()],
+    }
+}
+
+/*
+ * parse_rss
+ * collect the <item>s of an rss document, text and CDATA are both accepted
+ * and fields of the <channel> itself (its title, link...) are ignored
+ */
+pub fn parse_rss(xml: &str) -> Result<Vec<RssItem>> {
+    let mut reader = Reader::from_str(xml);
     reader.trim_text(true);
 
-    let mut count = 0;
-    let mut txt = Vec::new();
+    let mut items = Vec::new();
+    let mut item: Option<RssItem> = None;
+    // element currently being read inside an <item>, and its text so far
+    let mut field: Option<(Vec<u8>, String)> = None;
     let mut buf = Vec::new();
 
-    // The `Reader` does not implement `Iterator` because it outputs borrowed data (`Cow`s)
     loop {
-        // NOTE: this is the generic case when we don't know about the input BufRead.
-        // when the input is a &str or a &[u8], we don't actually need to use another
-        // buffer, we could directly call `reader.read_event_unbuffered()`
         match reader.read_event(&mut buf) {
             Ok(Event::Start(ref e)) => {
-                match e.name() {
-                    b"title" => txt.push(reader.read_text(b"title", &mut Vec::new()).unwrap()),
-                    b"tag2" => count += 1,
-                    _ => (),
+                if e.name() == b"item"
+                {
+                    item = Some(RssItem::default());
+                }
+                else if item.is_some()
+                {
+                    field = Some((e.name().to_vec(), String::new()));
+                }
+            }
+            Ok(Event::Text(ref e)) => {
+                if let Some((_, txt)) = field.as_mut()
+                {
+                    // unknown entities are kept as written rather than failing the feed
+                    match e.unescape_and_decode(&reader) {
+                        Ok(s) => txt.push_str(&s),
+                        Err(_) => txt.push_str(reader.decode(e)?),
+                    }
+                }
+            }
+            Ok(Event::CData(ref e)) => {
+                // quick-xml stores CDATA escaped, unescaping gives back the raw text
+                if let Some((_, txt)) = field.as_mut()
+                {
+                    txt.push_str(&e.unescape_and_decode(&reader)?);
+                }
+            }
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"item"
+                {
+                    if let Some(item) = item.take()
+                    {
+                        items.push(item);
+                    }
+                }
+                else if let (Some(item), Some((name, txt))) = (item.as_mut(), field.take())
+                {
+                    item.set(&name, txt.trim().to_string());
                 }
             }
-            Ok(Event::Eof) => break, // exits the loop when reaching end of file
-            Err(e) => panic!("Error at position {}: {:?}", reader.buffer_position(), e),
-            _ => (), // There are several other `Event`s we do not consider here
+            Ok(Event::Eof) => break,
+            Err(e) => bail!("rss error at position {}: {:?}", reader.buffer_position(), e),
+            _ => (),
         }
 
         // if we don't keep a borrow elsewhere, we can clear the buffer to keep memory usage low
         buf.clear();
     }
 
+    Ok(items)
+}
+
+pub async fn get_feed(url: &str) -> Result<Vec<RssItem>> {
+    let res = http::text(url).await?;
+    parse_rss(&res)
+}
+
+pub async fn crawl(url: &str) -> Result<Vec<RssItem>> {
+    get_feed(url).await
+}
+
+// one VEVENT of an icalendar document, dates are left as written
+#[derive(Debug, Default, Clone)]
+pub struct IcsEvent {
+    pub summary: String,
+    pub start: String,
+    pub end: String,
+    pub url: String,
+    pub uid: String,
+}
+
+// undo icalendar text escaping such as "\\", "\;", "\," and "\n"
+fn ics_unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\'
+        {
+            match chars.next() {
+                Some('n') | Some('N') => out.push('\n'),
+                Some(other) => out.push(other),
+                None => (),
+            }
+        }
+        else
+        {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/*
+ * parse_ics
+ * pull the VEVENTs out of an icalendar document,
+ * folded lines (continuations starting with a space or tab) are joined first
+ */
+pub fn parse_ics(ics: &str) -> Vec<IcsEvent> {
+    let mut lines: Vec<String> = vec![];
+    for line in ics.lines() {
+        match (line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')), lines.last_mut()) {
+            (Some(rest), Some(prev)) => prev.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let mut events = vec![];
+    let mut event: Option<IcsEvent> = None;
+    for line in &lines {
+        let (key, value) = match line.split_once(':') {
+            Some(kv) => kv,
+            None => continue,
+        };
+        // drop parameters such as DTSTART;VALUE=DATE
+        let name = key.split(';').next().unwrap_or(key);
+        match (name, event.as_mut()) {
+            ("BEGIN", _) if value == "VEVENT" => event = Some(IcsEvent::default()),
+            ("END", Some(_)) if value == "VEVENT" => events.extend(event.take()),
+            ("SUMMARY", Some(e)) => e.summary = ics_unescape(value),
+            ("DTSTART", Some(e)) => e.start = value.to_string(),
+            ("DTEND", Some(e)) => e.end = value.to_string(),
+            ("URL", Some(e)) => e.url = value.to_string(),
+            ("UID", Some(e)) => e.uid = value.to_string(),
+            _ => (),
+        }
+    }
+    events
+}
+
+pub async fn get_ics(url: &str) -> Result<Vec<IcsEvent>> {
+    let body = http::text(url).await?;
+    Ok(parse_ics(&body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEED: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<rss version="2.0">
+  <channel>
+    <title>CTFtime: Now running</title>
+    <link>https://ctftime.org/</link>
+    <item>
+      <title>Alpha &amp; Omega CTF</title>
+      <start_date>20260410T160000</start_date>
+      <finish_date>20260412T160000</finish_date>
+      <format>1</format>
+      <format_text>Jeopardy</format_text>
+      <weight>24.5</weight>
+      <ctftime_url>/event/1234/</ctftime_url>
+      <organizers><![CDATA[[{"id": 7, "name": "Team <One>"}]]]></organizers>
+      <description><![CDATA[<p>Fast & fun</p>]]></description>
+      <guid>https://ctftime.org/event/1234</guid>
+    </item>
+    <item>
+      <title>No Guid&nbsp;CTF</title>
+      <format>2</format>
+      <weight>n/a</weight>
+      <organizers>Somebody</organizers>
+    </item>
+  </channel>
+</rss>"#;
+
+    #[test]
+    fn rss_items_skip_the_channel() {
+        let items = parse_rss(FEED).unwrap();
+        assert_eq!(items.len(), 2);
+        assert!(items.iter().all(|i| !i.title.starts_with("CTFtime")));
+    }
+
+    #[test]
+    fn rss_entities_and_cdata() {
+        let items = parse_rss(FEED).unwrap();
+        let alpha = &items[0];
+        assert_eq!(alpha.title, "Alpha & Omega CTF");
+        assert_eq!(alpha.start_date, "20260410T160000");
+        assert_eq!(alpha.finish_date, "20260412T160000");
+        // the text wins over the numeric code
+        assert_eq!(alpha.format, "Jeopardy");
+        assert_eq!(alpha.weight, 24.5);
+        assert_eq!(alpha.organizers, vec!["Team <One>"]);
+        assert_eq!(alpha.extra.get("description").map(String::as_str), Some("<p>Fast & fun</p>"));
+        assert_eq!(event_id(&alpha.ctftime_url), Some(1234));
+        assert_eq!(event_id(&alpha.guid), Some(1234));
+    }
+
+    #[test]
+    fn rss_missing_fields() {
+        let items = parse_rss(FEED).unwrap();
+        let bare = &items[1];
+        // an unknown entity is kept as written
+        assert_eq!(bare.title, "No Guid&nbsp;CTF");
+        assert_eq!(bare.format, "2");
+        assert_eq!(bare.weight, 0.0);
+        assert_eq!(bare.organizers, vec!["Somebody"]);
+        assert!(bare.guid.is_empty() && bare.ctftime_url.is_empty());
+        assert_eq!(event_id(&bare.ctftime_url).or_else(|| event_id(&bare.guid)), None);
+    }
+
+    const CALENDAR: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+SUMMARY:CTFtime upcoming events\r
+X-WR-CALNAME:CTFtime\r
+BEGIN:VEVENT\r
+SUMMARY:Beta\\, Gamma \\; Delta CTF with a very long title that is\r
+  folded onto a second line\r
+DTSTART:20260410T160000Z\r
+DTEND:20260412T160000Z\r
+URL:https://ctftime.org/event/2345/\r
+UID:2345@ctftime.org\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+SUMMARY:All day\\nCTF\r
+DTSTART;VALUE=DATE:20260501\r
+DTEND;VALUE=DATE:20260503\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    #[test]
+    fn ics_events() {
+        let events = parse_ics(CALENDAR);
+        // the calendar's own summary is not an event
+        assert_eq!(events.len(), 2);
+        let beta = &events[0];
+        assert_eq!(beta.summary, "Beta, Gamma ; Delta CTF with a very long title that is folded onto a second line");
+        assert_eq!(beta.start, "20260410T160000Z");
+        assert_eq!(beta.end, "20260412T160000Z");
+        assert_eq!(event_id(&beta.url), Some(2345));
+    }
+
+    #[test]
+    fn ics_missing_fields() {
+        let events = parse_ics(CALENDAR);
+        let all_day = &events[1];
+        assert_eq!(all_day.summary, "All day\nCTF");
+        // parameters are dropped from the name, the value is left as written
+        assert_eq!(all_day.start, "20260501");
+        assert_eq!(all_day.end, "20260503");
+        assert!(all_day.url.is_empty() && all_day.uid.is_empty());
+        assert_eq!(event_id(&all_day.url).or_else(|| event_id(&all_day.uid)), None);
+        // an event cut off before END:VEVENT is dropped
+        assert!(parse_ics("BEGIN:VEVENT\nSUMMARY:x\n").is_empty());
+    }
+
+    #[test]
+    fn rss_broken_document() {
+        assert!(parse_rss("<rss><channel><item><title>x</wrong></item></channel></rss>").is_err());
+        assert!(parse_rss("").unwrap().is_empty());
+    }
+
+    const REGISTERED: &str = include_str!("fixtures/event_registered.html");
+    const NOT_REGISTERED: &str = include_str!("fixtures/event_not_registered.html");
+    const LOGIN: &str = include_str!("fixtures/login.html");
 
-    Ok(txt)
+    #[test]
+    fn event_account_registered_with_vote() {
+        assert_eq!(event_account(REGISTERED).unwrap(), (true, true));
+    }
+
+    #[test]
+    fn event_account_not_registered() {
+        // the join form doesn't count as a registration
+        assert_eq!(event_account(NOT_REGISTERED).unwrap(), (false, false));
+    }
+
+    #[test]
+    fn event_account_needs_a_session() {
+        let logged_out = REGISTERED.replace("/logout/", "/login/");
+        assert!(event_account(&logged_out).is_err());
+    }
+
+    #[test]
+    fn login_form_token() {
+        // the search form's token is not the one
+        assert_eq!(login_form(LOGIN), Some((String::from("/login/"), String::from("login-token"))));
+        assert_eq!(login_form(REGISTERED), None);
+    }
+
+    #[test]
+    fn flag_codes() {
+        assert_eq!(flag_code(Some("/stats/2026/de"), None).as_deref(), Some("DE"));
+        assert_eq!(flag_code(None, Some("/static/images/f/us.png")).as_deref(), Some("US"));
+        assert_eq!(flag_code(Some("/stats/2026"), Some("/static/images/f/us.png")).as_deref(), Some("US"));
+        assert_eq!(flag_code(None, Some("/static/images/f/unknown.png")), None);
+    }
 }
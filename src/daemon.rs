@@ -0,0 +1,371 @@
+use crate::config::{Config, Webhook, WebhookKind};
+use crate::http;
+use crate::loaders;
+use crate::notify::Watcher;
+use crate::store::Store;
+use chrono::{Datelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fs, path::PathBuf, time::Duration};
+
+/*
+ * Snapshot
+ * what the daemon saw on its previous check, kept on disk
+ * so a restart does not announce everything again
+ */
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Snapshot {
+    watcher: Watcher,
+    // (global rank, rating points) of our team
+    team_rank: Option<(u32, f64)>,
+}
+
+impl Snapshot {
+    fn path() -> Option<PathBuf> {
+        Some(Config::data_dir()?.join("daemon_snapshot.json"))
+    }
+
+    fn load() -> Snapshot {
+        Snapshot::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = Snapshot::path().ok_or("no data directory")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+fn log(msg: &str) {
+    println!("[{}] {}", Utc::now().format("%Y-%m-%d %H:%M:%S"), msg);
+}
+
+fn history(res: Option<rusqlite::Result<()>>) {
+    if let Some(Err(e)) = res
+    {
+        log(&format!("history: {}", e));
+    }
+}
+
+/*
+ * check
+ * run the loaders once and return a message for everything that changed
+ */
+async fn check(config: &Config, snapshot: &mut Snapshot, store: Option<&Store>) -> Vec<String> {
+    let mut msgs = vec![];
+    let watched = |title: &str| config.watchlist.iter().any(|w| w.eq_ignore_ascii_case(title.trim()));
+    let filter = &config.notifications.filter;
+
+    let mut running = vec![];
+    match loaders::load_current_events(&mut running, &config.sources).await {
+        Ok(_) => {
+            history(store.map(|s| s.record_running(&running)));
+            msgs.extend(snapshot.watcher.running(&running));
+        }
+        Err(e) => log(&format!("running feed: {}", e)),
+    }
+
+    let mut upcoming = vec![];
+    match loaders::load_upcoming_events(&mut upcoming, 14, &config.sources).await {
+        Ok(_) => {
+            history(store.map(|s| s.record_upcoming(&upcoming)));
+            msgs.extend(snapshot.watcher.new_upcoming(&upcoming, filter));
+            msgs.extend(snapshot.watcher.upcoming(&upcoming, config.notifications.lead_minutes, filter, watched));
+        }
+        Err(e) => log(&format!("upcoming events: {}", e)),
+    }
+
+    let mut writeups = vec![];
+    match loaders::load_writeups(&mut writeups, 1, &config.sources).await {
+        Ok(_) => {
+            history(store.map(|s| s.record_writeups(&writeups)));
+            msgs.extend(snapshot.watcher.writeups(&writeups, watched));
+        }
+        Err(e) => log(&format!("writeups: {}", e)),
+    }
+
+    // the current year's global leaderboard, the tui compares with these snapshots
+    let year = Utc::now().year();
+    let mut stats = vec![];
+    match loaders::load_leaderboard(&mut stats, year, None).await {
+        Ok(_) => history(store.map(|s| s.record_rankings(year, None, &stats))),
+        Err(e) => log(&format!("leaderboard: {}", e)),
+    }
+
+    if let Some(team_id) = config.daemon.team_id.or(config.team.id)
+    {
+        match loaders::load_team_history(team_id).await {
+            Ok(team) => {
+                let current = team.years.iter()
+                    .find(|y| y.0 == year)
+                    .and_then(|y| Some((y.2?, y.1)));
+                if let (Some((old_rank, old_points)), Some((rank, points))) = (snapshot.team_rank, current)
+                {
+                    if rank != old_rank
+                    {
+                        let arrow = if rank < old_rank { "up" } else { "down" };
+                        msgs.push(format!(
+                            "{} moved {} from #{} to #{} ({:.2} -> {:.2} points)",
+                            team.name, arrow, old_rank, rank, old_points, points
+                        ));
+                    }
+                }
+                if current.is_some()
+                {
+                    snapshot.team_rank = current;
+                }
+            }
+            Err(e) => log(&format!("team {}: {}", team_id, e)),
+        }
+    }
+
+    msgs
+}
+
+/*
+ * cycle
+ * one check, every message is logged and posted to the webhooks
+ */
+async fn cycle(config: &Config, snapshot: &mut Snapshot, store: Option<&Store>) -> Vec<String> {
+    let msgs = check(config, snapshot, store).await;
+    for msg in &msgs
+    {
+        log(msg);
+        for hook in &config.daemon.webhooks
+        {
+            if let Err(e) = post(http::client(), hook, msg).await
+            {
+                log(&format!("webhook: {}", e));
+            }
+        }
+    }
+    msgs
+}
+
+// body for a webhook of the given kind
+fn payload(kind: WebhookKind, msg: &str) -> serde_json::Value {
+    match kind {
+        WebhookKind::Discord => serde_json::json!({ "content": msg, "username": "ctfterm" }),
+        WebhookKind::Slack => serde_json::json!({ "text": msg }),
+        WebhookKind::Matrix => serde_json::json!({ "text": msg, "username": "ctfterm" }),
+    }
+}
+
+async fn post(client: &reqwest::Client, hook: &Webhook, msg: &str) -> Result<(), Box<dyn Error>> {
+    let resp = client.post(&hook.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(payload(hook.kind, msg).to_string())
+        .send()
+        .await?;
+    if !resp.status().is_success()
+    {
+        return Err(format!("{} returned {}", hook.url, resp.status()).into());
+    }
+    Ok(())
+}
+
+/*
+ * run
+ * `ctfterm daemon`: check ctftime on a schedule without the tui
+ * and push what changed to the configured webhooks
+ */
+pub async fn run(config: Config) -> Result<(), Box<dyn Error>> {
+    if config.daemon.webhooks.is_empty()
+    {
+        log("no [[daemon.webhooks]] configured, messages are only printed");
+    }
+
+    let mut snapshot = Snapshot::load();
+    let store = Store::open().map_err(|e| log(&format!("history: {}", e))).ok();
+    let mut interval = tokio::time::interval(Duration::from_secs(config.daemon.interval_secs.max(10)));
+    loop {
+        interval.tick().await;
+        cycle(&config, &mut snapshot, store.as_ref()).await;
+        if let Err(e) = snapshot.save()
+        {
+            log(&format!("snapshot: {}", e));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Source;
+    use std::{
+        collections::HashMap,
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        sync::{Arc, Mutex},
+    };
+
+    /*
+     * Stub
+     * a tiny http server standing in for ctftime and the webhooks:
+     * GETs are answered from `pages` by path, POST bodies are collected in `posts`
+     */
+    #[derive(Clone, Default)]
+    struct Stub {
+        pages: Arc<Mutex<HashMap<String, String>>>,
+        posts: Arc<Mutex<Vec<(String, String)>>>,
+    }
+
+    impl Stub {
+        fn start(&self) -> String {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = listener.local_addr().unwrap();
+            let stub = self.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let mut reader = BufReader::new(stream);
+                    let mut request = String::new();
+                    let mut length = 0;
+                    reader.read_line(&mut request).unwrap();
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).unwrap();
+                        if line.trim().is_empty()
+                        {
+                            break;
+                        }
+                        if let Some((name, value)) = line.split_once(':')
+                        {
+                            if name.eq_ignore_ascii_case("content-length")
+                            {
+                                length = value.trim().parse().unwrap();
+                            }
+                        }
+                    }
+                    let mut body = vec![0; length];
+                    reader.read_exact(&mut body).unwrap();
+
+                    let mut parts = request.split_whitespace();
+                    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+                    let path = target.split('?').next().unwrap_or("").to_string();
+                    let (status, page) = if method == "POST" {
+                        stub.posts.lock().unwrap().push((path, String::from_utf8(body).unwrap()));
+                        ("200 OK", String::new())
+                    } else {
+                        match stub.pages.lock().unwrap().get(&path) {
+                            Some(page) => ("200 OK", page.clone()),
+                            None => ("404 Not Found", String::new()),
+                        }
+                    };
+                    let mut stream = reader.into_inner();
+                    let _ = write!(stream, "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, page.len(), page);
+                }
+            });
+            format!("http://{}", addr)
+        }
+
+        fn page(&self, path: &str, body: String) {
+            self.pages.lock().unwrap().insert(path.to_string(), body);
+        }
+    }
+
+    fn rss(titles: &[&str]) -> String {
+        let items: String = titles.iter()
+            .map(|t| format!("<item><title>{}</title><start_date>20260101T000000</start_date><finish_date>20260103T000000</finish_date></item>", t))
+            .collect();
+        format!("<?xml version=\"1.0\"?><rss><channel><title>CTFtime</title>{}</channel></rss>", items)
+    }
+
+    fn upcoming(events: &[(u32, &str, chrono::Duration)]) -> String {
+        let events: Vec<serde_json::Value> = events.iter().map(|(id, title, starts_in)| {
+            let start = Utc::now() + *starts_in;
+            serde_json::json!({
+                "id": id,
+                "title": title,
+                "start": start.to_rfc3339(),
+                "finish": (start + chrono::Duration::days(2)).to_rfc3339(),
+                "format": "Jeopardy",
+                "weight": 25.0,
+            })
+        }).collect();
+        serde_json::Value::Array(events).to_string()
+    }
+
+    fn writeups(rows: &[(&str, &str, &str)]) -> String {
+        let rows: String = rows.iter()
+            .map(|(event, task, author)| format!("<tr><td>{}</td><td>{}</td><td>web</td><td>{}</td><td>read</td></tr>", event, task, author))
+            .collect();
+        format!("<html><body><table>{}</table></body></html>", rows)
+    }
+
+    fn team(place: u32, points: f64) -> String {
+        let year = Utc::now().year().to_string();
+        serde_json::json!({
+            "id": 7,
+            "name": "Team",
+            "rating": { year: { "rating_place": place, "rating_points": points } },
+        }).to_string()
+    }
+
+    #[tokio::test]
+    async fn cycle_posts_changes_to_webhooks() {
+        let stub = Stub::default();
+        let base = stub.start();
+        let mut config = Config::default();
+        config.http.base_url = base.clone();
+        config.http.retries = 0;
+        config.http.min_interval_ms = 0;
+        config.http.cache = false;
+        config.sources.upcoming = Source::Api;
+        config.sources.writeups = Source::Html;
+        config.sources.running_rss = format!("{}/running.rss", base);
+        config.watchlist = vec![String::from("Alpha CTF")];
+        config.notifications.filter.enabled = true;
+        config.daemon.team_id = Some(7);
+        config.daemon.webhooks = vec![
+            Webhook { kind: WebhookKind::Slack, url: format!("{}/slack", base) },
+            Webhook { kind: WebhookKind::Discord, url: format!("{}/discord", base) },
+        ];
+        // fails rather than letting the cycle reach ctftime.org when
+        // another test set up the shared client first
+        http::init(&config.http).expect("the shared client points at the stub");
+
+        // the first cycle only records what is there
+        stub.page("/running.rss", rss(&["Alpha CTF"]));
+        stub.page("/api/v1/events/", upcoming(&[(1, "Beta CTF", chrono::Duration::days(3))]));
+        stub.page("/writeups", writeups(&[("Alpha CTF", "pwn1", "alice")]));
+        stub.page("/api/v1/teams/7/", team(20, 100.0));
+        stub.page("/team/7", String::from("<html></html>"));
+        let mut snapshot = Snapshot::default();
+        assert!(cycle(&config, &mut snapshot, None).await.is_empty());
+        assert!(stub.posts.lock().unwrap().is_empty());
+
+        stub.page("/running.rss", rss(&["Alpha CTF", "Gamma CTF"]));
+        stub.page("/api/v1/events/", upcoming(&[
+            (1, "Beta CTF", chrono::Duration::days(3)),
+            (2, "Delta CTF", chrono::Duration::minutes(10)),
+        ]));
+        stub.page("/writeups", writeups(&[("Alpha CTF", "web2", "bob"), ("Alpha CTF", "pwn1", "alice")]));
+        stub.page("/api/v1/teams/7/", team(15, 150.0));
+        let msgs = cycle(&config, &mut snapshot, None).await;
+        assert_eq!(msgs.len(), 5, "{:?}", msgs);
+        assert_eq!(msgs[0], "Now running: Gamma CTF");
+        assert!(msgs[1].starts_with("New upcoming event: Delta CTF (Jeopardy, weight 25.00) starts "));
+        assert!(msgs[2].starts_with("Delta CTF starts in "));
+        assert_eq!(msgs[3], "New writeup for Alpha CTF: web2 by bob");
+        assert_eq!(msgs[4], "Team moved up from #20 to #15 (100.00 -> 150.00 points)");
+
+        // every message goes to every hook in its own format
+        let posts = stub.posts.lock().unwrap().clone();
+        assert_eq!(posts.len(), 10);
+        for (msg, pair) in msgs.iter().zip(posts.chunks(2))
+        {
+            let slack: serde_json::Value = serde_json::from_str(&pair[0].1).unwrap();
+            let discord: serde_json::Value = serde_json::from_str(&pair[1].1).unwrap();
+            assert_eq!(pair[0].0, "/slack");
+            assert_eq!(slack, serde_json::json!({ "text": msg }));
+            assert_eq!(pair[1].0, "/discord");
+            assert_eq!(discord, serde_json::json!({ "content": msg, "username": "ctfterm" }));
+        }
+    }
+}
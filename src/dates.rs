@@ -0,0 +1,94 @@
+use crate::config::{DateFormat, TimeConfig};
+use chrono::{DateTime, Duration, Local, TimeZone, Utc};
+use chrono_tz::Tz;
+
+// where dates are shown
+#[derive(Clone, Copy)]
+enum Zone {
+    Local,
+    Utc,
+    Named(Tz),
+}
+
+/*
+ * Clock
+ * turns the UTC timestamps the loaders produce into text
+ * in the configured timezone and format
+ */
+#[derive(Clone, Copy)]
+pub struct Clock {
+    zone: Zone,
+    pub format: DateFormat,
+}
+
+impl Clock {
+    // unknown timezone names fall back to the local timezone
+    pub fn from_config(config: &TimeConfig) -> Clock {
+        let zone = match config.timezone.trim() {
+            "" => Zone::Local,
+            tz if tz.eq_ignore_ascii_case("local") => Zone::Local,
+            tz if tz.eq_ignore_ascii_case("utc") => Zone::Utc,
+            tz => tz.parse::<Tz>().map(Zone::Named).unwrap_or(Zone::Local),
+        };
+        Clock { zone, format: config.format }
+    }
+
+    fn absolute(&self, date: DateTime<Utc>, fmt: &str) -> String {
+        match self.zone {
+            Zone::Local => date.with_timezone(&Local).format(fmt).to_string(),
+            Zone::Utc => date.format(fmt).to_string(),
+            Zone::Named(tz) => tz.from_utc_datetime(&date.naive_utc()).format(fmt).to_string(),
+        }
+    }
+
+    pub fn date(&self, date: DateTime<Utc>) -> String {
+        match self.format {
+            DateFormat::Absolute => match self.zone {
+                // local offsets have no name, the offset would only clutter the lists
+                Zone::Local => self.absolute(date, "%b %d %H:%M"),
+                _ => self.absolute(date, "%b %d %H:%M %Z"),
+            },
+            DateFormat::Iso => self.absolute(date, "%Y-%m-%dT%H:%M%:z"),
+            DateFormat::Relative => relative(date - Utc::now()),
+        }
+    }
+
+    // "-" when the date is not known
+    pub fn maybe(&self, date: Option<DateTime<Utc>>) -> String {
+        date.map(|d| self.date(d)).unwrap_or_else(|| String::from("-"))
+    }
+
+    // "start - finish (2d 0h)"
+    pub fn range(&self, start: DateTime<Utc>, finish: DateTime<Utc>) -> String {
+        format!("{} - {} ({})", self.date(start), self.date(finish), format_duration(finish - start))
+    }
+}
+
+// e.g. "2d 3h", "3h 12m" or "12m"
+pub fn format_duration(d: Duration) -> String {
+    let mins = d.num_minutes();
+    if mins >= 24 * 60
+    {
+        format!("{}d {}h", mins / (24 * 60), mins % (24 * 60) / 60)
+    }
+    else if mins >= 60
+    {
+        format!("{}h {}m", mins / 60, mins % 60)
+    }
+    else
+    {
+        format!("{}m", mins)
+    }
+}
+
+// "in 3h 12m" for the future, "2d 4h ago" for the past
+pub fn relative(d: Duration) -> String {
+    if d >= Duration::zero()
+    {
+        format!("in {}", format_duration(d))
+    }
+    else
+    {
+        format!("{} ago", format_duration(-d))
+    }
+}
@@ -0,0 +1,276 @@
+use crate::config::{Config, HttpConfig};
+use chrono::{DateTime, Utc};
+use reqwest::{
+    cookie::{CookieStore, Jar},
+    header::{HeaderName, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, REFERER, RETRY_AFTER, SET_COOKIE},
+    Certificate, Client, Proxy, Response, StatusCode, Url,
+};
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fs, path::PathBuf, sync::{Arc, Mutex, OnceLock}, time::Duration};
+use tokio::time::Instant;
+
+// longest a Retry-After is waited for, a later retry is left to the next refresh
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(300);
+
+/*
+ * Http
+ * the client shared by every fetch so connections are reused,
+ * with the retry, rate and cache settings of [http]
+ */
+struct Http {
+    client: Client,
+    base: Url,
+    retries: u32,
+    backoff: Duration,
+    interval: Duration,
+    cache: bool,
+    // earliest time the next request may start
+    next: Mutex<Option<Instant>>,
+}
+
+static HTTP: OnceLock<Http> = OnceLock::new();
+// cookies of every response, the ctftime session once logged in
+static JAR: OnceLock<Arc<Jar>> = OnceLock::new();
+
+const CTFTIME: &str = "https://ctftime.org/";
+
+fn jar() -> Arc<Jar> {
+    JAR.get_or_init(|| Arc::new(Jar::default())).clone()
+}
+
+fn build(config: &HttpConfig) -> Result<Http, Box<dyn Error>> {
+    let mut builder = Client::builder()
+        .user_agent(config.user_agent.as_str())
+        .timeout(Duration::from_secs(config.timeout_secs.max(1)))
+        .connect_timeout(Duration::from_secs(config.connect_timeout_secs.max(1)))
+        .cookie_provider(jar());
+    if let Some(proxy) = &config.proxy
+    {
+        builder = builder.proxy(Proxy::all(proxy.as_str()).map_err(|e| format!("proxy {}: {}", proxy, e))?);
+    }
+    if let Some(path) = &config.ca_bundle
+    {
+        let pem = fs::read_to_string(path).map_err(|e| format!("ca bundle {}: {}", path.display(), e))?;
+        // a bundle holds several certificates, each is added on its own
+        for cert in pem.split_inclusive("-----END CERTIFICATE-----").filter(|c| c.contains("-----BEGIN CERTIFICATE-----"))
+        {
+            builder = builder.add_root_certificate(Certificate::from_pem(cert.trim().as_bytes())?);
+        }
+    }
+    let base = Url::parse(&config.base_url).map_err(|e| format!("base url {}: {}", config.base_url, e))?;
+    Ok(Http::new(builder.build()?, base, config))
+}
+
+impl Http {
+    fn new(client: Client, base: Url, config: &HttpConfig) -> Http {
+        Http {
+            client,
+            base,
+            retries: config.retries,
+            backoff: Duration::from_millis(config.backoff_ms),
+            interval: Duration::from_millis(config.min_interval_ms),
+            cache: config.cache,
+            next: Mutex::new(None),
+        }
+    }
+
+    // how long to wait for our turn, requests are spaced `interval` apart
+    fn slot(&self) -> Duration {
+        let now = Instant::now();
+        let mut next = self.next.lock().unwrap_or_else(|e| e.into_inner());
+        let start = next.map_or(now, |n| n.max(now));
+        *next = Some(start + self.interval);
+        start - now
+    }
+
+    // keep every request away for a while, e.g. after a 429
+    fn hold_off(&self, wait: Duration) {
+        let until = Instant::now() + wait;
+        let mut next = self.next.lock().unwrap_or_else(|e| e.into_inner());
+        *next = Some(next.map_or(until, |n| n.max(until)));
+    }
+}
+
+// set up the shared client from the config, before the first fetch;
+// an error once a client exists, it would not use this config
+pub fn init(config: &HttpConfig) -> Result<(), Box<dyn Error>> {
+    let http = build(config)?;
+    HTTP.set(http).map_err(|_| "the http client is already set up")?;
+    Ok(())
+}
+
+fn http() -> &'static Http {
+    HTTP.get_or_init(|| {
+        let config = HttpConfig::default();
+        build(&config).unwrap_or_else(|_| {
+            let client = Client::builder().cookie_provider(jar()).build().unwrap_or_default();
+            let base = Url::parse(CTFTIME).expect("ctftime url");
+            Http::new(client, base, &config)
+        })
+    })
+}
+
+pub fn client() -> &'static Client {
+    &http().client
+}
+
+// a ctftime page or api path such as "/event/1234" under the configured base url
+pub fn url(path: &str) -> String {
+    format!("{}{}", http().base.as_str().trim_end_matches('/'), path)
+}
+
+// a cookie for ctftime, e.g. a session from the config
+pub fn set_cookie(name: &str, value: &str) {
+    jar().add_cookie_str(&format!("{}={}; Path=/", name, value), &http().base);
+}
+
+pub fn cookie(name: &str) -> Option<String> {
+    let cookies = jar().cookies(&http().base)?;
+    cookies.to_str().ok()?
+        .split(';')
+        .filter_map(|c| c.trim().split_once('='))
+        .find(|(n, _)| *n == name)
+        .map(|(_, v)| v.to_string())
+}
+
+// worth another try: the server is overloaded or asks us to slow down
+fn retryable(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+fn header(res: &Response, name: HeaderName) -> Option<String> {
+    res.headers().get(name)?.to_str().ok().map(String::from)
+}
+
+// Retry-After as seconds or as an http date
+fn retry_after(res: &Response) -> Option<Duration> {
+    let value = header(res, RETRY_AFTER)?;
+    let wait = match value.trim().parse::<u64>() {
+        Ok(secs) => Duration::from_secs(secs),
+        Err(_) => {
+            let at = DateTime::parse_from_rfc2822(value.trim()).ok()?.with_timezone(&Utc);
+            (at - Utc::now()).to_std().unwrap_or_default()
+        }
+    };
+    Some(wait.min(MAX_RETRY_AFTER))
+}
+
+/*
+ * Cached
+ * a page with its validators, kept in $XDG_CACHE_HOME/ctfterm/http
+ * so an unchanged page is answered with 304 instead of sent again
+ */
+#[derive(Serialize, Deserialize)]
+struct Cached {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: String,
+}
+
+impl Cached {
+    // fnv-1a of the url, stable across builds unlike the std hasher
+    fn path(url: &str) -> Option<PathBuf> {
+        let hash = url.bytes().fold(0xcbf29ce484222325u64, |h, b| (h ^ b as u64).wrapping_mul(0x100000001b3));
+        Some(Config::cache_dir()?.join("http").join(format!("{:016x}.json", hash)))
+    }
+
+    fn load(url: &str) -> Option<Cached> {
+        let cached: Cached = serde_json::from_str(&fs::read_to_string(Cached::path(url)?).ok()?).ok()?;
+        if cached.url == url { Some(cached) } else { None }
+    }
+
+    // a failed write only costs a full download next time
+    fn save(&self) {
+        if let Some(path) = Cached::path(&self.url)
+        {
+            if let Some(dir) = path.parent()
+            {
+                let _ = fs::create_dir_all(dir);
+            }
+            if let Ok(json) = serde_json::to_string(self)
+            {
+                let _ = fs::write(path, json);
+            }
+        }
+    }
+}
+
+/*
+ * text
+ * GET a page through the shared client and return its body.
+ * requests wait for their turn under the global rate, a cached copy is revalidated
+ * with If-None-Match / If-Modified-Since, and timeouts, failed connections and
+ * 5xx / 429 answers are retried after Retry-After or an exponential backoff.
+ * an error status, also the last one after the retries, is an Err
+ */
+pub async fn text(url: impl AsRef<str>) -> reqwest::Result<String> {
+    let url = url.as_ref();
+    let http = http();
+    // pages seen with the login session are neither reused nor kept
+    let cache = http.cache && cookie("sessionid").is_none();
+    let cached = if cache { Cached::load(url) } else { None };
+    let mut attempt = 0;
+    loop {
+        tokio::time::sleep(http.slot()).await;
+        let mut req = http.client.get(url);
+        if let Some(cached) = &cached
+        {
+            if let Some(etag) = &cached.etag
+            {
+                req = req.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(modified) = &cached.last_modified
+            {
+                req = req.header(IF_MODIFIED_SINCE, modified);
+            }
+        }
+        let backoff = http.backoff * 2u32.saturating_pow(attempt);
+        match req.send().await {
+            Ok(res) if res.status() == StatusCode::NOT_MODIFIED && cached.is_some() => {
+                return Ok(cached.map(|c| c.body).unwrap_or_default());
+            }
+            Ok(res) if retryable(res.status()) => {
+                // everyone waits, not just this request
+                http.hold_off(retry_after(&res).unwrap_or(backoff));
+                if attempt >= http.retries
+                {
+                    // out of retries, the error status is the outcome
+                    res.error_for_status()?;
+                }
+            }
+            Ok(res) => {
+                // 404, 403 and such are errors, not pages to parse
+                let res = res.error_for_status()?;
+                let (etag, last_modified) = (header(&res, ETAG), header(&res, LAST_MODIFIED));
+                // pages setting cookies, like the login form's csrf token, are not reused
+                let fresh = res.status().is_success() && !res.headers().contains_key(SET_COOKIE);
+                let body = res.text().await?;
+                if cache && fresh && (etag.is_some() || last_modified.is_some())
+                {
+                    Cached { url: url.to_string(), etag, last_modified, body: body.clone() }.save();
+                }
+                return Ok(body);
+            }
+            Err(e) if (e.is_timeout() || e.is_connect()) && attempt < http.retries => {
+                tokio::time::sleep(backoff).await;
+            }
+            Err(e) => return Err(e),
+        }
+        attempt += 1;
+    }
+}
+
+// POST a form such as the login, after waiting for our turn like every other request
+pub async fn post_form(url: &str, form: &[(&str, &str)], referer: &str) -> reqwest::Result<String> {
+    let http = http();
+    tokio::time::sleep(http.slot()).await;
+    http.client.post(url)
+        .header(REFERER, referer)
+        .form(form)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await
+}
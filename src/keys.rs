@@ -0,0 +1,137 @@
+use crate::config::Panel;
+use crate::types::{App, Detail, MainView, Tab};
+use crossterm::event::KeyCode;
+
+/*
+ * Binding
+ * keys of handle_key, listed by the help overlay while `active` holds.
+ * handle_key ignores a key unless one of its bindings is active,
+ * so the help and what the keys do cannot drift apart
+ */
+pub struct Binding {
+    pub key: &'static str,
+    codes: &'static [KeyCode],
+    pub action: &'static str,
+    active: fn(&App) -> bool,
+}
+
+fn always(_: &App) -> bool {
+    true
+}
+
+fn dashboard(app: &App) -> bool {
+    app.tab == Tab::Dashboard
+}
+
+fn leaderboard(app: &App) -> bool {
+    app.tab != Tab::MyTeam && app.focused == Panel::Leaderboard
+}
+
+// the My Team tab lists results, not events that can be followed
+fn followable(app: &App) -> bool {
+    app.tab != Tab::MyTeam && app.focused != Panel::Leaderboard
+}
+
+fn main_panel(app: &App) -> bool {
+    app.tab == Tab::Dashboard || app.tab == Tab::Events
+}
+
+fn running(app: &App) -> bool {
+    main_panel(app) && app.config.layout.shows(Panel::Running)
+}
+
+fn drills(app: &App) -> bool {
+    app.tab == Tab::MyTeam
+        || matches!(app.focused, Panel::Running | Panel::Leaderboard | Panel::Watchlist)
+        || (app.focused == Panel::Main && matches!(app.main_view, MainView::Upcoming | MainView::PastEvents))
+}
+
+fn upcoming(app: &App) -> bool {
+    app.tab != Tab::MyTeam && app.focused == Panel::Main && app.main_view == MainView::Upcoming
+}
+
+fn schedule(app: &App) -> bool {
+    main_panel(app) && app.main_view == MainView::Schedule
+}
+
+fn sortable(app: &App) -> bool {
+    app.tab != Tab::MyTeam
+        && (app.focused == Panel::Leaderboard || (app.focused == Panel::Main && app.main_view == MainView::PastEvents))
+}
+
+// an upcoming event is open in the details
+pub fn what_if(app: &App) -> bool {
+    match app.history.current {
+        Some(Detail::Event(id)) if app.tab != Tab::Dashboard => app.events.get(&id)
+            .and_then(|e| e.start)
+            .is_some_and(|start| start > chrono::Utc::now()),
+        _ => false,
+    }
+}
+
+fn history(app: &App) -> bool {
+    app.tab != Tab::Dashboard
+}
+
+pub const BINDINGS: &[Binding] = &[
+    Binding { key: "q / Ctrl+C", codes: &[KeyCode::Char('q')], action: "quit", active: always },
+    Binding { key: "?", codes: &[KeyCode::Char('?')], action: "show or hide this help", active: always },
+    Binding { key: "Esc", codes: &[KeyCode::Esc], action: "close the help, dismiss the error in the status bar", active: always },
+    Binding { key: "Tab / Shift+Tab", codes: &[KeyCode::Tab, KeyCode::BackTab], action: "next / previous tab", active: always },
+    Binding { key: "1 - 6", codes: &[KeyCode::Char('1'), KeyCode::Char('2'), KeyCode::Char('3'), KeyCode::Char('4'), KeyCode::Char('5'), KeyCode::Char('6')], action: "jump to a tab", active: always },
+    Binding { key: "w / s", codes: &[KeyCode::Char('w'), KeyCode::Char('s')], action: "focus the next / previous panel", active: always },
+    Binding { key: "d / a", codes: &[KeyCode::Char('d'), KeyCode::Char('a')], action: "scroll down / up, more pages load at the end", active: always },
+    Binding { key: "z", codes: &[KeyCode::Char('z')], action: "maximize the focused panel", active: dashboard },
+    Binding { key: "Enter", codes: &[KeyCode::Enter], action: "open the selected event or team", active: drills },
+    Binding { key: "b / n", codes: &[KeyCode::Char('b'), KeyCode::Char('n')], action: "back / forward through opened events and teams", active: history },
+    Binding { key: "f", codes: &[KeyCode::Char('f')], action: "follow or unfollow the selected event", active: followable },
+    Binding { key: "[ / ]", codes: &[KeyCode::Char('['), KeyCode::Char(']')], action: "previous / next leaderboard year", active: always },
+    Binding { key: "g", codes: &[KeyCode::Char('g')], action: "global or country leaderboard", active: always },
+    Binding { key: "m", codes: &[KeyCode::Char('m')], action: "sort the leaderboard by rank or by places climbed since the year's first snapshot", active: always },
+    Binding { key: "l", codes: &[KeyCode::Char('l')], action: "only teams from the selected team's country, or all teams", active: leaderboard },
+    Binding { key: "e", codes: &[KeyCode::Char('e')], action: "academic teams, non-academic teams or all teams", active: leaderboard },
+    Binding { key: "o", codes: &[KeyCode::Char('o')], action: "cycle the sort column and direction", active: sortable },
+    Binding { key: "c", codes: &[KeyCode::Char('c')], action: "chart the selected team", active: leaderboard },
+    Binding { key: "x", codes: &[KeyCode::Char('x')], action: "compare the selected team", active: leaderboard },
+    Binding { key: "v", codes: &[KeyCode::Char('v')], action: "rating charts in place of past events", active: main_panel },
+    Binding { key: "u", codes: &[KeyCode::Char('u')], action: "upcoming events in place of past events", active: main_panel },
+    Binding { key: "t", codes: &[KeyCode::Char('t')], action: "running events as a ticker", active: running },
+    Binding { key: "r", codes: &[KeyCode::Char('r')], action: "mark the event playing / maybe / skip", active: upcoming },
+    Binding { key: "p", codes: &[KeyCode::Char('p')], action: "weekly schedule of the marked events", active: main_panel },
+    Binding { key: ", / .", codes: &[KeyCode::Char(','), KeyCode::Char('.')], action: "previous / next week", active: schedule },
+    Binding { key: "+ / -", codes: &[KeyCode::Char('+'), KeyCode::Char('-')], action: "better / worse place in the what if calculator", active: what_if },
+    Binding { key: "< / >", codes: &[KeyCode::Char('<'), KeyCode::Char('>')], action: "less / more of the winner's points in the what if calculator", active: what_if },
+];
+
+// the bindings that do something in the current tab and panel
+pub fn active(app: &App) -> impl Iterator<Item = &'static Binding> + '_ {
+    BINDINGS.iter().filter(move |b| (b.active)(app))
+}
+
+// whether handle_key should act on the key in the current tab and panel
+pub fn bound(app: &App, key: KeyCode) -> bool {
+    active(app).any(|b| b.codes.contains(&key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every code a binding dispatches on shows in the label the help prints
+    #[test]
+    fn labels_match_codes() {
+        for b in BINDINGS
+        {
+            assert!(!b.codes.is_empty(), "{}", b.key);
+            for code in b.codes
+            {
+                let label = match code {
+                    KeyCode::Char(c) => c.to_string(),
+                    KeyCode::BackTab => String::from("Shift+Tab"),
+                    other => format!("{:?}", other),
+                };
+                assert!(b.key.contains(&label) || b.key == "1 - 6", "{} does not list {}", b.key, label);
+            }
+        }
+    }
+}
@@ -1,83 +1,478 @@
-use std::{error::Error, io};
-use futures::executor::block_on;
+use std::io;
 // just 'mod crawler' doesnt work for whatever
 // fucking reason >:I
 #[path = "crawler.rs"] mod crawler;
+use crate::session::Credentials;
+use crate::types::{Account, AccountEvent, Comparison, EventDetail, EventResult, PastEvent, RunningEvent, ScoreRow, TeamHistory, TeamStat, UpcomingEvent};
+use crate::config::{Source, SourcesConfig};
+use crate::countries;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
 
-pub async fn load_past_events(mut past_events_items: &mut Vec<(String, String)>)->io::Result<()>
+fn crawl_err(e: crawler::Error) -> io::Error
 {
-    // get past events
-    let past_evts: Vec<Vec<String>> = crawler::get_past_events().await.unwrap();
-    for evt in &past_evts {
-        if evt.len() > 0 {
-            past_events_items.push((evt[0].clone(), evt[1].clone()));
+    io::Error::other(e.to_string())
+}
+
+fn unsupported(source: Source, panel: &str) -> io::Error
+{
+    io::Error::new(io::ErrorKind::Unsupported, format!("{:?} is not a source for {}", source, panel))
+}
+
+pub async fn load_past_events(past_events_items: &mut Vec<PastEvent>, page: u32, sources: &SourcesConfig)->io::Result<()>
+{
+    match sources.past_events {
+        Source::Html => {
+            // get past events
+            let past_evts = crawler::get_past_events(page).await.map_err(crawl_err)?;
+            // name, date, format, location, weight, notes ("123 teams total")
+            for (evt, id) in past_evts {
+                if let [title, date, rest @ ..] = &evt[..] {
+                    let (start, finish) = ctftime_range(date);
+                    let weight = rest.get(2).and_then(|w| w.parse().ok());
+                    let participants = rest.iter()
+                        .find(|cell| cell.contains("teams"))
+                        .and_then(|cell| cell.split_whitespace().next()?.parse().ok());
+                    past_events_items.push(PastEvent { id, title: title.clone(), start, finish, date: date.clone(), weight, participants });
+                }
+            }
+        }
+        Source::Rss => {
+            // a feed has no further pages
+            if page > 1
+            {
+                return Ok(());
+            }
+            let evts = crawler::get_feed(&sources.past_events_rss).await.map_err(crawl_err)?;
+            for evt in evts {
+                past_events_items.push(PastEvent {
+                    id: crawler::event_id(&evt.ctftime_url).or_else(|| crawler::event_id(&evt.guid)),
+                    start: feed_date(&evt.start_date),
+                    finish: feed_date(&evt.finish_date),
+                    date: format!("{} — {}", evt.start_date, evt.finish_date),
+                    weight: Some(evt.weight),
+                    participants: None,
+                    title: evt.title,
+                });
+            }
         }
+        source => return Err(unsupported(source, "past events")),
     }
     Ok(())
 }
 
-pub async fn load_leaderboard(mut leaderboard_items: &mut Vec<(String, String, String, String)>)->io::Result<()>
+pub async fn load_leaderboard(leaderboard_items: &mut Vec<TeamStat>, year: i32, country: Option<&str>)->io::Result<()>
 {
     // get leaderboard stats
-    let leaderboard_stats: Vec<Vec<String>> = crawler::get_stats().await.unwrap();
-    // update app with leaderboard stats
-    for stat in &leaderboard_stats {
-        leaderboard_items.push(
-            (
-                stat[0].clone(), 
-                stat[1].clone(), 
-                stat[3].clone(), 
-                stat[2].clone()
-            )
-        );
+    let (headers, leaderboard_stats) = crawler::get_stats(year, country).await.map_err(crawl_err)?;
+
+    // locate columns by their header label, falling back to the global table layout
+    let team_idx = column(&headers, &["Team"], 1);
+    let country_idx = column(&headers, &["Country"], 2);
+    let points_idx = column(&headers, &["Points", "Rating"], 3);
+
+    // update app with leaderboard stats, a country table has no flags but every team is from there
+    for row in &leaderboard_stats {
+        let code = row.country.clone().or_else(|| country.and_then(countries::normalize));
+        let country = match &code {
+            Some(code) => countries::name(code).map(String::from).unwrap_or_else(|| code.clone()),
+            None => cell(&row.cells, country_idx),
+        };
+        leaderboard_items.push(TeamStat {
+            rank: cell(&row.cells, 0),
+            name: cell(&row.cells, team_idx),
+            points: cell(&row.cells, points_idx),
+            country,
+            country_code: code,
+            team_id: row.team_id,
+        });
+    }
+    Ok(())
+}
+
+// index of the header labelled with one of `names`
+fn column(headers: &[String], names: &[&str], fallback: usize) -> usize {
+    headers.iter()
+        .position(|h| names.iter().any(|n| h.eq_ignore_ascii_case(n)))
+        .unwrap_or(fallback)
+}
+
+fn cell(cells: &[String], idx: usize) -> String {
+    cells.get(idx).cloned().unwrap_or_default()
+}
+
+/*
+ * load_scoreboard
+ * places and ctf points of every team in a finished event,
+ * empty for events that have no scoreboard yet
+ */
+pub async fn load_scoreboard(event_id: u32)->io::Result<Vec<ScoreRow>>
+{
+    let (headers, rows) = crawler::get_scoreboard(event_id).await.map_err(crawl_err)?;
+    let place_idx = column(&headers, &["Place", "#"], 0);
+    let team_idx = column(&headers, &["Team"], 1);
+    let points_idx = column(&headers, &["CTF points", "Points"], 2);
+    Ok(rows.iter()
+        .filter_map(|row| Some(ScoreRow {
+            place: cell(&row.cells, place_idx).trim().parse().ok()?,
+            team: cell(&row.cells, team_idx),
+            team_id: row.team_id,
+            points: number(&cell(&row.cells, points_idx)),
+        }))
+        .collect())
+}
+
+// parse a scraped number such as "1,234.56", treating junk as zero
+fn number(s: &str) -> f64
+{
+    s.replace(',', "").trim().parse().unwrap_or(0.0)
+}
+
+pub async fn load_event(event_id: u32)->io::Result<EventDetail>
+{
+    let evt = crawler::get_event(event_id).await.map_err(crawl_err)?;
+    let date = |s: &str| DateTime::parse_from_rfc3339(s).map(|d| d.with_timezone(&Utc)).ok();
+    Ok(EventDetail {
+        id: evt.id,
+        start: date(&evt.start),
+        finish: date(&evt.finish),
+        title: evt.title,
+        format: evt.format,
+        weight: evt.weight,
+        onsite: evt.onsite,
+        location: evt.location,
+        url: evt.url,
+        participants: evt.participants,
+        organizers: evt.organizers.into_iter().map(|o| o.name).collect(),
+        description: evt.description.trim().to_string(),
+    })
+}
+
+// whether a team is academic, from its ctftime api entry
+pub async fn load_academic(team_id: u32)->io::Result<bool>
+{
+    Ok(crawler::get_team(team_id).await.map_err(crawl_err)?.academic)
+}
+
+pub async fn load_team_history(team_id: u32)->io::Result<TeamHistory>
+{
+    let info = crawler::get_team(team_id).await.map_err(crawl_err)?;
+    let results = crawler::get_team_results(team_id).await.map_err(crawl_err)?;
+
+    let mut years: Vec<(i32, f64, Option<u32>, Option<u32>)> = info.rating.iter()
+        .filter_map(|(year, r)| Some((year.parse().ok()?, r.rating_points.unwrap_or(0.0), r.rating_place, r.country_place)))
+        .collect();
+    years.sort_by_key(|y| y.0);
+
+    // the team page lists the newest event first
+    let mut results: Vec<EventResult> = results.iter().rev().map(|(year, row, event_id)| EventResult {
+        year: *year,
+        event_id: *event_id,
+        place: number(&row[0]) as u32,
+        event: row[1].clone(),
+        rating_points: number(&row[3]),
+    }).collect();
+    results.sort_by_key(|r| r.year);
+
+    Ok(TeamHistory {
+        id: info.id,
+        name: info.name,
+        country: info.country,
+        academic: info.academic,
+        years,
+        results,
+    })
+}
+
+pub async fn load_comparison(a: u32, b: u32)->io::Result<Comparison>
+{
+    let (a, b) = futures::join!(load_team_history(a), load_team_history(b));
+    Ok(Comparison::new(a?, b?))
+}
+
+pub async fn load_upcoming_events(upcoming_items: &mut Vec<UpcomingEvent>, days: i64, sources: &SourcesConfig)->io::Result<()>
+{
+    let horizon = Utc::now() + chrono::Duration::days(days);
+    match sources.upcoming {
+        Source::Api => {
+            let events = crawler::get_upcoming_events(days).await.map_err(crawl_err)?;
+            let date = |s: &str| DateTime::parse_from_rfc3339(s).map(|d| d.with_timezone(&Utc)).ok();
+            for evt in events {
+                let (start, finish) = match (date(&evt.start), date(&evt.finish)) {
+                    (Some(start), Some(finish)) => (start, finish),
+                    _ => continue,
+                };
+                upcoming_items.push(UpcomingEvent {
+                    id: Some(evt.id),
+                    title: evt.title,
+                    start,
+                    finish,
+                    format: evt.format,
+                    weight: evt.weight,
+                    onsite: evt.onsite,
+                });
+            }
+        }
+        Source::Rss => {
+            let evts = crawler::get_feed(&sources.upcoming_rss).await.map_err(crawl_err)?;
+            for evt in evts {
+                let (start, finish) = match (feed_date(&evt.start_date), feed_date(&evt.finish_date)) {
+                    (Some(start), Some(finish)) => (start, finish),
+                    _ => continue,
+                };
+                let id = crawler::event_id(&evt.ctftime_url).or_else(|| crawler::event_id(&evt.guid));
+                let onsite = evt.extra.get("onsite").map(|v| v.eq_ignore_ascii_case("true")).unwrap_or(false);
+                upcoming_items.push(UpcomingEvent {
+                    id,
+                    title: evt.title,
+                    start,
+                    finish,
+                    format: evt.format,
+                    weight: evt.weight,
+                    onsite,
+                });
+            }
+        }
+        Source::Ics => {
+            let evts = crawler::get_ics(&sources.upcoming_ics).await.map_err(crawl_err)?;
+            for evt in evts {
+                let (start, finish) = match (feed_date(&evt.start), feed_date(&evt.end)) {
+                    (Some(start), Some(finish)) => (start, finish),
+                    _ => continue,
+                };
+                let id = crawler::event_id(&evt.url).or_else(|| crawler::event_id(&evt.uid));
+                // the calendar carries no format, weight or location
+                upcoming_items.push(UpcomingEvent {
+                    id,
+                    title: evt.summary,
+                    start,
+                    finish,
+                    format: String::new(),
+                    weight: 0.0,
+                    onsite: false,
+                });
+            }
+        }
+        source => return Err(unsupported(source, "upcoming events")),
     }
+    // feeds list everything, keep the same window the api is asked for
+    upcoming_items.retain(|e| e.finish > Utc::now() && e.start <= horizon);
+    upcoming_items.sort_by_key(|e| e.start);
     Ok(())
 }
 
-pub async fn load_current_events(mut current_events_items: &mut Vec<String>)->io::Result<()>
+/*
+ * ctftime_range
+ * start and finish of ctftime's date text on the past events page,
+ * "Fri, 08 April 2022, 16:00 UTC — Sun, 10 April 2022, 16:00 UTC"
+ */
+fn ctftime_range(s: &str) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>)
+{
+    let date = |s: &str| {
+        let s = s.trim();
+        // the weekday is redundant and sometimes missing
+        let s = match s.split_once(", ") {
+            Some((day, rest)) if day.len() <= 3 && day.chars().all(char::is_alphabetic) => rest,
+            _ => s,
+        };
+        let s = s.trim_end_matches("UTC").trim();
+        NaiveDateTime::parse_from_str(s, "%d %B %Y, %H:%M")
+            .or_else(|_| NaiveDateTime::parse_from_str(s, "%d %b %Y, %H:%M"))
+            .map(|d| Utc.from_utc_datetime(&d))
+            .ok()
+    };
+    match s.split_once('—').or_else(|| s.split_once(" - ")) {
+        Some((start, finish)) => (date(start), date(finish)),
+        None => (date(s), None),
+    }
+}
+
+// parse a feed date, ctftime uses "20220402T000000" (utc) in its rss,
+// calendars add a trailing "Z" or give a bare "20220402"
+fn feed_date(s: &str) -> Option<DateTime<Utc>>
+{
+    let s = s.trim();
+    let compact = s.trim_end_matches('Z');
+    NaiveDateTime::parse_from_str(compact, "%Y%m%dT%H%M%S")
+        .or_else(|_| NaiveDate::parse_from_str(compact, "%Y%m%d").map(|d| d.and_hms_opt(0, 0, 0).unwrap_or_default()))
+        .map(|d| Utc.from_utc_datetime(&d))
+        .or_else(|_| DateTime::parse_from_rfc3339(s).map(|d| d.with_timezone(&Utc)))
+        .ok()
+}
+
+pub async fn load_current_events(current_events_items: &mut Vec<RunningEvent>, sources: &SourcesConfig)->io::Result<()>
 {
     // get current events
-    let evts: Vec<String> = crawler::crawl().await.unwrap();
-    let mut s = String::from("");
+    let evts = crawler::crawl(&sources.running_rss).await.map_err(crawl_err)?;
 
     // update app with running events
-    let mut i: usize = 0;
-    for evt in &evts{
-        if i > 0 {
-            for ch in evts[i].chars()
+    for evt in evts {
+        current_events_items.push(RunningEvent {
+            id: crawler::event_id(&evt.ctftime_url).or_else(|| crawler::event_id(&evt.guid)),
+            start: feed_date(&evt.start_date),
+            finish: feed_date(&evt.finish_date),
+            title: evt.title,
+            format: evt.format,
+            weight: evt.weight,
+            organizers: evt.organizers,
+        });
+    }
+    Ok(())
+}
+
+pub async fn load_writeups(writeup_items: &mut Vec<crate::types::Writeup>, page: u32, sources: &SourcesConfig)->io::Result<()>
+{
+    match sources.writeups {
+        Source::Html => {
+            // get write ups
+            let writeups: Vec<Vec<String>> = crawler::get_writeups(page).await.map_err(crawl_err)?;
+            // update app with writeups
+            for writeup in writeups.iter().filter(|w| w.len() >= 5) {
+                writeup_items.push(
+                    (
+                        writeup[0].clone(), 
+                        writeup[1].clone(), 
+                        writeup[2].clone(), 
+                        writeup[3].clone(), 
+                        writeup[4].clone()
+                    )
+                );
+            }
+        }
+        Source::Rss => {
+            // a feed has no further pages
+            if page > 1
             {
-                s.push_str( &String::from(" | ") );
-                s.push_str( &evts[i].clone());
+                return Ok(());
+            }
+            let items = crawler::get_feed(&sources.writeups_rss).await.map_err(crawl_err)?;
+            for mut item in items {
+                let field = |item: &mut crawler::RssItem, names: &[&str]| {
+                    names.iter().find_map(|n| item.extra.remove(*n)).unwrap_or_default()
+                };
+                let mut event = field(&mut item, &["ctf_name", "ctf", "event"]);
+                let mut task = field(&mut item, &["task_name", "task"]);
+                // otherwise fall back to titles written as "<event> - <task>"
+                if event.is_empty()
+                {
+                    match item.title.split_once(" - ") {
+                        Some((e, t)) => {
+                            event = e.to_string();
+                            task = t.to_string();
+                        }
+                        None => task = item.title.clone(),
+                    }
+                }
+                let tags = field(&mut item, &["tags"]);
+                let author = field(&mut item, &["author", "team", "dc:creator"]);
+                writeup_items.push((event, task, tags, author, item.link));
             }
         }
-        i += 1;
-    }
-    
-    if i < 2
-    {
-        s.push_str( &String::from(" | ") );
-        s.push_str( &String:: from( "None" ) );
+        source => return Err(unsupported(source, "writeups")),
     }
-    current_events_items.push( s.clone() );
+
     Ok(())
 }
 
-pub async fn load_writeups(mut writeup_items: &mut Vec<(String,String,String,String,String)>)->io::Result<()>
-{
-    // get write ups
-    let writeups: Vec<Vec<String>> = crawler::get_writeups().await.unwrap();
-    // update app with writeups
-    for writeup in &writeups {
-        writeup_items.push(
-            (
-                writeup[0].clone(), 
-                writeup[1].clone(), 
-                writeup[2].clone(), 
-                writeup[3].clone(), 
-                writeup[4].clone()
-            )
-        );
+/*
+ * load_login
+ * log in with saved credentials and return the user name,
+ * a session cookie is checked by loading a page with it
+ */
+pub async fn load_login(credentials: Credentials)->io::Result<String>
+{
+    match &credentials {
+        Credentials::Cookie { sessionid } => crate::http::set_cookie("sessionid", sessionid),
+        Credentials::Password { username, password } => {
+            crawler::login(username, password).await.map_err(crawl_err)?;
+            // ctftime only hands out a session for the right password
+            if crate::http::cookie("sessionid").is_none()
+            {
+                return Err(io::Error::other("wrong username or password"));
+            }
+        }
+    }
+    match crawler::get_user().await.map_err(crawl_err)? {
+        Some(user) => Ok(user),
+        None => Err(io::Error::other("ctftime did not accept the login")),
     }
+}
 
-    Ok(())
+/*
+ * load_account
+ * look up which of the given upcoming events our team registered for
+ * and which of the recent past events still take our weight vote
+ */
+// at most this many event pages are checked for registrations, and as many for votes
+const ACCOUNT_EVENTS: usize = 15;
+
+// (registered, vote open) of each event, the pages are requested together
+// and the shared client still spaces them out
+async fn event_accounts(events: Vec<AccountEvent>)->io::Result<Vec<(AccountEvent, (bool, bool))>>
+{
+    futures::future::try_join_all(events.into_iter().take(ACCOUNT_EVENTS).map(|evt| async move {
+        let state = crawler::get_event_account(evt.id).await.map_err(crawl_err)?;
+        Ok::<_, io::Error>((evt, state))
+    })).await
+}
+
+pub async fn load_account(upcoming: Vec<AccountEvent>, past: Vec<AccountEvent>)->io::Result<Account>
+{
+    let (upcoming, past) = futures::try_join!(event_accounts(upcoming), event_accounts(past))?;
+    Ok(Account {
+        registrations: upcoming.into_iter().filter(|(_, (registered, _))| *registered).map(|(evt, _)| evt).collect(),
+        votes: past.into_iter().filter(|(_, (_, vote_open))| *vote_open).map(|(evt, _)| evt).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{DateFormat, TimeConfig};
+    use crate::dates::Clock;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn range_across_midnight() {
+        let (start, finish) = ctftime_range("Sat, 31 December 2022, 22:00 UTC — Sun, 01 January 2023, 02:00 UTC");
+        assert_eq!(start, Some(utc(2022, 12, 31, 22, 0)));
+        assert_eq!(finish, Some(utc(2023, 1, 1, 2, 0)));
+        assert_eq!(finish.zip(start).map(|(f, s)| (f - s).num_hours()), Some(4));
+        // abbreviated months, no weekday and a plain dash
+        let (start, finish) = ctftime_range("08 Apr 2022, 23:30 UTC - 09 Apr 2022, 00:30 UTC");
+        assert_eq!(start, Some(utc(2022, 4, 8, 23, 30)));
+        assert_eq!(finish, Some(utc(2022, 4, 9, 0, 30)));
+    }
+
+    #[test]
+    fn range_unparsable() {
+        assert_eq!(ctftime_range(""), (None, None));
+        assert_eq!(ctftime_range("Fri, 08 April 2022, 16:00 UTC"), (Some(utc(2022, 4, 8, 16, 0)), None));
+        assert_eq!(ctftime_range("soon — later"), (None, None));
+    }
+
+    #[test]
+    fn range_across_dst_change() {
+        // europe moved its clocks forward at 01:00 UTC on 29 March 2026
+        let (start, finish) = ctftime_range("Sat, 28 March 2026, 12:00 UTC — Sun, 29 March 2026, 12:00 UTC");
+        let (start, finish) = (start.unwrap(), finish.unwrap());
+        assert_eq!((finish - start).num_hours(), 24);
+        let clock = Clock::from_config(&TimeConfig { timezone: String::from("Europe/Berlin"), format: DateFormat::Absolute });
+        assert_eq!(clock.range(start, finish), "Mar 28 13:00 CET - Mar 29 14:00 CEST (1d 0h)");
+        let iso = Clock::from_config(&TimeConfig { timezone: String::from("Europe/Berlin"), format: DateFormat::Iso });
+        assert_eq!(iso.date(finish), "2026-03-29T14:00+02:00");
+    }
+
+    #[test]
+    fn feed_dates() {
+        assert_eq!(feed_date("20260329T003000"), Some(utc(2026, 3, 29, 0, 30)));
+        assert_eq!(feed_date(" 20260329T013000Z "), Some(utc(2026, 3, 29, 1, 30)));
+        assert_eq!(feed_date("20260329"), Some(utc(2026, 3, 29, 0, 0)));
+        // offsets are taken into account, crossing midnight back into the previous day
+        assert_eq!(feed_date("2026-03-29T01:30:00+02:00"), Some(utc(2026, 3, 28, 23, 30)));
+        assert_eq!(feed_date(""), None);
+        assert_eq!(feed_date("tomorrow"), None);
+    }
 }
@@ -1,438 +1,2114 @@
-use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use std::{
+    error::Error,
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    time::Duration,
 };
-use std::{error::Error, io};
 use tui::{
     backend::{Backend, CrosstermBackend},
-    layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    symbols,
     text::{Span,Spans},
-    widgets::{List, ListItem, ListState, Block, BorderType, Borders, Cell, Row, Table, TableState},
+    widgets::{Axis, Chart, Clear, Dataset, GraphType, List, ListItem, Block, Borders, Cell, Paragraph, Row, Table, TableState, Tabs, Wrap},
     Frame, Terminal,
 };
-use tokio::runtime::Runtime;
-use tokio::time::*;
-use tokio::task;
-use tokio::sync::Mutex;
-use futures::executor::block_on;
+mod config;
+mod countries;
+mod daemon;
+mod dates;
+mod http;
 mod loaders;
+mod keys;
+mod notify;
+mod rating;
+mod schedule;
+mod session;
+mod store;
+mod term;
+mod theme;
 mod types;
 
+use config::Panel;
+use dates::format_duration;
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    // setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
+    let mut config = config::Config::load()?;
+    // what was picked in the tui last time, the daemon follows the same watchlist
+    config::UiState::load().apply(&mut config);
+    http::init(&config.http)?;
+
+    // `ctfterm daemon` watches ctftime for the webhooks without a tui
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("daemon") => return daemon::run(config).await,
+        Some("history") => {
+            if let Err(e) = store::run(&args[1..])
+            {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some("login" | "logout") => {
+            if let Err(e) = session::run(&config, &args).await
+            {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        _ => (),
+    }
+
+    // setup terminal, it is restored on every way out: quit, error, panic and signals
+    term::install_panic_hook();
+    if let Err(err) = term::enter()
+    {
+        term::restore();
+        return Err(err.into());
+    }
+    let backend = CrosstermBackend::new(io::stdout());
     let mut terminal = Terminal::new(backend)?;
-    
+
     // initialize app
-    let mut app = types::App::new();
-
-    let res = run_app(&mut terminal, &mut app).await;
-    // restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
-
-    /*if let Err(err) = res {
-        println!("{:?}", err)
-    }*/
+    let mut app = types::App::new(config);
+
+    let quit = Arc::new(AtomicBool::new(false));
+    term::spawn_signal_handler(quit.clone());
+
+    let res = run_app(&mut terminal, &mut app, quit).await;
+    term::restore();
+
+    // only printed once the alternate screen is gone
+    for panic in term::panics()
+    {
+        eprintln!("ctfterm: {}", panic);
+    }
+    if let Err(err) = res {
+        eprintln!("ctfterm: {}", err);
+        std::process::exit(1);
+    }
 
     Ok(())
 }
 
-async fn run_app<'a,B: Backend>(terminal: &mut Terminal<B>, app: &'a mut types::App<'a>) -> io::Result<()> {
-	let up_input = std::sync::Mutex::new(0);
-    let up = std::sync::Arc::new(up_input);
-    let inputThreadUp = up.clone();
-
-	let down_input = std::sync::Mutex::new(0);
-    let down = std::sync::Arc::new(down_input);
-    let inputThreadDown = down.clone();
-
-	let right_input = std::sync::Mutex::new(0);
-    let right = std::sync::Arc::new(right_input);
-    let inputThreadRight = right.clone();
-
-	let left_input = std::sync::Mutex::new(0);
-    let left = std::sync::Arc::new(left_input);
-    let inputThreadLeft = left.clone();
-
-	let x = std::sync::Mutex::new(0);
-	let arc = std::sync::Arc::new(x);	
-	let inputThreadRunning = arc.clone();
-    let dataThreadRunning = arc.clone();
-
-
-	let mut scrollCounter = 0;
-	let scrollTimer = 100;
-	let mut autoscroll = true;
-
-
-	let loaded = std::sync::Arc::new( std::sync::Mutex::new(0) );
-	let loaded1_ = loaded.clone();
-	let loaded2_ = loaded.clone();
-	let loaded3_ = loaded.clone();
-	let loaded4_ = loaded.clone();
-    let load_total = 4;
-
-	let needs_load = std::sync::Arc::new( std::sync::Mutex::new(true) );
-    let needs_load_ = needs_load.clone();
-
-    let results =  tokio::join!(
-                    tokio::spawn( 
-                            async move {
-                            let mut curr_events = vec![];
-                            loaders::load_current_events(&mut curr_events).await;
-                            *loaded1_.lock().unwrap() += 1;
-                            return curr_events;
-                        }
-                    ),
-                    tokio::spawn( 
-                        async move {
-                            let mut stats = vec![];
-                            loaders::load_leaderboard(&mut stats).await;
-                            *loaded2_.lock().unwrap() += 1;
-                            return stats;
-                        }
-                    ),
-                    tokio::spawn( 
-                        async move {
-                            let mut past_events = vec![];
-                            loaders::load_past_events(&mut past_events).await;
-                            *loaded3_.lock().unwrap() += 1;
-                            return past_events;
-                        }
-                    ),
-                    tokio::spawn( 
-                        async move {
-                            let mut writeups = vec![];
-                            loaders::load_writeups(&mut writeups).await;
-                            *loaded4_.lock().unwrap() += 1;
-                            return writeups;
-                        }
-                    ));
-    let results_ = std::sync::Arc::new( std::sync::Mutex::new( results));
+/*
+ * request_pages
+ * start a background fetch for the next page of any paginated
+ * panel whose selection is close to the end of its items
+ */
+fn request_pages(app: &mut types::App, tx: &mpsc::Sender<types::Loaded>) {
+    let prefetch = app.config.pagination.prefetch_rows;
+
+    if app.past_events_pager.wants_next(app.past_events_list.remaining(), prefetch)
+    {
+        let page = app.past_events_pager.begin();
+        let tx = tx.clone();
+        let sources = app.config.sources.clone();
+        tokio::spawn(async move {
+            let mut past_events = vec![];
+            let res = loaders::load_past_events(&mut past_events, page, &sources).await;
+            let _ = tx.send(types::Loaded::PastEvents(page, res.map(|_| past_events)));
+        });
+    }
+
+    if app.writeups_pager.wants_next(app.writeups.remaining(), prefetch)
+    {
+        let page = app.writeups_pager.begin();
+        let tx = tx.clone();
+        let sources = app.config.sources.clone();
+        tokio::spawn(async move {
+            let mut writeups = vec![];
+            let res = loaders::load_writeups(&mut writeups, page, &sources).await;
+            let _ = tx.send(types::Loaded::Writeups(page, res.map(|_| writeups)));
+        });
+    }
+}
+
+/*
+ * request_leaderboard
+ * refetch the leaderboard for the year/country currently selected in the config
+ */
+fn request_leaderboard(app: &types::App, tx: &mpsc::Sender<types::Loaded>) {
+    let year = app.leaderboard_year();
+    let country = app.leaderboard_country();
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        let mut stats = vec![];
+        let res = loaders::load_leaderboard(&mut stats, year, country.as_deref()).await;
+        let _ = tx.send(types::Loaded::Leaderboard { year, country, res: res.map(|_| stats) });
+    });
+}
+
+// remember the new leaderboard selection and fetch it
+fn leaderboard_changed(app: &mut types::App, tx: &mpsc::Sender<types::Loaded>) {
+    app.save_state();
+    request_leaderboard(app, tx);
+}
+
+/*
+ * snapshot
+ * write fetched rows to the history database, failures only show in the status bar
+ */
+fn snapshot(app: &mut types::App, write: impl FnOnce(&store::Store) -> rusqlite::Result<()>) {
+    if let Some(store) = &app.store
+    {
+        if let Err(e) = write(store)
+        {
+            app.error = Some(format!("history: {}", e));
+        }
+    }
+}
+
+/*
+ * apply_loaded
+ * hand a finished background fetch to its panel
+ */
+fn apply_loaded(app: &mut types::App, loaded: types::Loaded) {
+    for (source, error) in loaded.outcomes()
+    {
+        app.record(source, error);
+    }
+    match loaded {
+        types::Loaded::PastEvents(page, Ok(items)) => {
+            snapshot(app, |s| s.record_past(&items));
+            app.past_events_list.update(&items);
+            app.sort_past_events();
+            app.past_events_pager.finish(page, items.len());
+        }
+        types::Loaded::PastEvents(_, Err(_)) => app.past_events_pager.fail(),
+        types::Loaded::Writeups(page, Ok(items)) => {
+            snapshot(app, |s| s.record_writeups(&items));
+            app.writeups.update(&items);
+            app.writeups_pager.finish(page, items.len());
+        }
+        types::Loaded::Writeups(_, Err(_)) => app.writeups_pager.fail(),
+        types::Loaded::Leaderboard { year, country, res: Ok(items) } => {
+            snapshot(app, |s| s.record_rankings(year, country.as_deref(), &items));
+            // shown only if the selection didn't move on while it was loading
+            if (year, country) == (app.leaderboard_year(), app.leaderboard_country())
+            {
+                app.leaderboard_stats.replace(items);
+                app.leaderboard_loaded();
+            }
+        }
+        // a failed refetch keeps the teams already shown, the status bar has the error
+        types::Loaded::Leaderboard { res: Err(_), .. } => (),
+        types::Loaded::Academic(id, res) => {
+            app.academic_pending.remove(&id);
+            if let Ok(academic) = res
+            {
+                app.academic.insert(id, academic);
+                app.filter_leaderboard();
+            }
+        }
+        types::Loaded::TeamHistory(Ok(history)) => {
+            if app.chart_teams.len() < types::MAX_CHART_TEAMS
+                && !app.chart_teams.iter().any(|t| t.id == history.id)
+            {
+                app.chart_teams.push(history);
+            }
+        }
+        types::Loaded::TeamHistory(Err(_)) => (),
+        types::Loaded::Comparison(Ok(comparison)) => {
+            app.comparison = Some(comparison);
+            app.main_view = types::MainView::Compare;
+        }
+        types::Loaded::Comparison(Err(_)) => (),
+        types::Loaded::EventDetail(id, Ok(event)) => {
+            app.events.insert(id, event);
+        }
+        types::Loaded::Scoreboard(id, Ok(rows)) => {
+            app.scoreboards.insert(id, rows);
+        }
+        // events without results yet, the status bar tells about failures
+        types::Loaded::Scoreboard(_, Err(_)) => (),
+        types::Loaded::TeamDetail(id, Ok(team)) => {
+            if app.config.team.id == Some(id)
+            {
+                app.my_team.refresh(team.results.clone());
+            }
+            app.teams.insert(id, team);
+        }
+        types::Loaded::Login(Ok(user)) => app.user = Some(user),
+        types::Loaded::Account(Ok(account)) => app.account = Some(account),
+        // the status bar shows the error, the panels keep what they had
+        types::Loaded::Login(Err(_)) => app.user = None,
+        types::Loaded::Account(Err(_)) => (),
+        // the status bar shows the error, opening the detail again retries
+        types::Loaded::EventDetail(_, Err(_)) | types::Loaded::TeamDetail(_, Err(_)) => (),
+        types::Loaded::Refresh { running, upcoming, writeups } => {
+            let mut msgs = vec![];
+            if let Ok(events) = running
+            {
+                snapshot(app, |s| s.record_running(&events));
+                msgs.extend(app.watcher.running(&events));
+                app.curr_events.refresh(events);
+            }
+            if let Ok(events) = upcoming
+            {
+                snapshot(app, |s| s.record_upcoming(&events));
+                app.upcoming.refresh(events);
+            }
+            if let Ok(rows) = writeups
+            {
+                snapshot(app, |s| s.record_writeups(&rows));
+                let watchlist = &app.watchlist.items;
+                msgs.extend(app.watcher.writeups(&rows, |event| {
+                    watchlist.iter().any(|w| w.eq_ignore_ascii_case(event.trim()))
+                }));
+            }
+            for msg in msgs
+            {
+                notify::deliver(&app.config.notifications, &msg, &mut app.toasts);
+            }
+        }
+    }
+}
+
+/*
+ * check_reminders
+ * announce watched or filtered events that are about to start
+ */
+fn check_reminders(app: &mut types::App) {
+    let watchlist = &app.watchlist.items;
+    let msgs = app.watcher.upcoming(
+        &app.upcoming.items,
+        app.config.notifications.lead_minutes,
+        &app.config.notifications.filter,
+        |title| watchlist.iter().any(|w| w.eq_ignore_ascii_case(title.trim())),
+    );
+    for msg in msgs
+    {
+        notify::deliver(&app.config.notifications, &msg, &mut app.toasts);
+    }
+    let now = std::time::Instant::now();
+    app.toasts.retain(|t| t.until > now);
+}
+
+/*
+ * toggle_chart_team
+ * add the selected leaderboard team to the chart view, or remove it if already plotted
+ */
+fn toggle_chart_team(app: &mut types::App, tx: &mpsc::Sender<types::Loaded>) {
+    let team_id = match app.leaderboard_stats.state.selected()
+        .and_then(|i| app.leaderboard_stats.items.get(i))
+        .and_then(|stat| stat.team_id)
+    {
+        Some(id) => id,
+        None => return,
+    };
+
+    if let Some(idx) = app.chart_teams.iter().position(|t| t.id == team_id)
+    {
+        app.chart_teams.remove(idx);
+        return;
+    }
+    if app.chart_teams.len() >= types::MAX_CHART_TEAMS
+    {
+        return;
+    }
+
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        let res = loaders::load_team_history(team_id).await;
+        let _ = tx.send(types::Loaded::TeamHistory(res));
+    });
+    app.main_view = types::MainView::Charts;
+}
+
+/*
+ * pick_compare_team
+ * the first pick remembers the selected leaderboard team,
+ * the second loads both teams into the comparison view
+ */
+fn pick_compare_team(app: &mut types::App, tx: &mpsc::Sender<types::Loaded>) {
+    let team_id = match app.leaderboard_stats.state.selected()
+        .and_then(|i| app.leaderboard_stats.items.get(i))
+        .and_then(|stat| stat.team_id)
+    {
+        Some(id) => id,
+        None => return,
+    };
+
+    match app.compare_pick.take() {
+        None => app.compare_pick = Some(team_id),
+        Some(first) if first == team_id => (),
+        Some(first) => {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                let res = loaders::load_comparison(first, team_id).await;
+                let _ = tx.send(types::Loaded::Comparison(res));
+            });
+        }
+    }
+}
+
+/*
+ * cycle_focus
+ * move focus to the next (or previous) panel shown by the layout or the tab
+ */
+fn cycle_focus(app: &mut types::App, step: i32) {
+    let visible = match app.tab {
+        types::Tab::Dashboard => app.config.layout.visible(),
+        tab => tab.panels().to_vec(),
+    };
+    if visible.is_empty()
+    {
+        return;
+    }
+    let pos = visible.iter().position(|p| *p == app.focused).unwrap_or(0) as i32;
+    let len = visible.len() as i32;
+    app.focused = visible[(pos + step).rem_euclid(len) as usize];
+}
+
+/*
+ * select_tab
+ * switch screens, focusing the tab's first list and loading the own team when needed
+ */
+fn select_tab(app: &mut types::App, tab: types::Tab, tx: &mpsc::Sender<types::Loaded>) {
+    app.tab = tab;
+    app.maximized = false;
+    if let Some(first) = tab.panels().first()
+    {
+        if !tab.panels().contains(&app.focused)
+        {
+            app.focused = *first;
+        }
+    }
+    if tab == types::Tab::MyTeam
+    {
+        if let Some(team_id) = app.config.team.id
+        {
+            request_detail(app, types::Detail::Team(team_id), tx);
+        }
+        request_account(app, tx);
+    }
+}
+
+/*
+ * request_account
+ * when logged in, check the events of the coming month for our registrations
+ * and the events of the last two weeks for open weight votes
+ */
+fn request_account(app: &types::App, tx: &mpsc::Sender<types::Loaded>) {
+    if app.user.is_none()
+    {
+        return;
+    }
+    let now = chrono::Utc::now();
+    let upcoming: Vec<types::AccountEvent> = app.upcoming.items.iter()
+        .filter(|e| e.start < now + chrono::Duration::days(30))
+        .filter_map(|e| Some(types::AccountEvent { id: e.id?, title: e.title.clone(), start: Some(e.start) }))
+        .collect();
+    let past: Vec<types::AccountEvent> = app.past_events_list.items.iter()
+        .filter(|e| e.finish.is_some_and(|f| f > now - chrono::Duration::days(14)))
+        .filter_map(|e| Some(types::AccountEvent { id: e.id?, title: e.title.clone(), start: e.start }))
+        .collect();
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        let _ = tx.send(types::Loaded::Account(loaders::load_account(upcoming, past).await));
+    });
+}
+
+/*
+ * request_academic
+ * while the academic filter is on, look up the status of the
+ * leaderboard teams not known yet, the shared client spaces the requests out
+ */
+fn request_academic(app: &mut types::App, tx: &mpsc::Sender<types::Loaded>) {
+    if app.academic_filter == types::AcademicFilter::All
+    {
+        return;
+    }
+    let ids: Vec<u32> = app.leaderboard_stats.items.iter().chain(&app.leaderboard_hidden)
+        .filter_map(|s| s.team_id)
+        .filter(|id| !app.academic.contains_key(id) && !app.academic_pending.contains(id))
+        .collect();
+    for id in ids
+    {
+        app.academic_pending.insert(id);
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let _ = tx.send(types::Loaded::Academic(id, loaders::load_academic(id).await));
+        });
+    }
+}
+
+// fetch an event or team unless it was already loaded
+fn request_detail(app: &types::App, detail: types::Detail, tx: &mpsc::Sender<types::Loaded>) {
+    let tx = tx.clone();
+    match detail {
+        types::Detail::Event(id) => {
+            if !app.events.contains_key(&id)
+            {
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    let _ = tx.send(types::Loaded::EventDetail(id, loaders::load_event(id).await));
+                });
+            }
+            // the results view, empty until the event is over
+            if !app.scoreboards.contains_key(&id)
+            {
+                tokio::spawn(async move {
+                    let _ = tx.send(types::Loaded::Scoreboard(id, loaders::load_scoreboard(id).await));
+                });
+            }
+        }
+        types::Detail::Team(id) if !app.teams.contains_key(&id) => {
+            tokio::spawn(async move {
+                let _ = tx.send(types::Loaded::TeamDetail(id, loaders::load_team_history(id).await));
+            });
+        }
+        _ => (),
+    }
+}
+
+/*
+ * open_detail
+ * drill into the event or team selected in the focused list,
+ * from the dashboard this jumps to the tab showing that list
+ */
+fn open_detail(app: &mut types::App, tx: &mpsc::Sender<types::Loaded>) {
+    let selected = |state: &tui::widgets::ListState| state.selected();
+    let detail = if app.tab == types::Tab::MyTeam
+    {
+        selected(&app.my_team.state)
+            .and_then(|i| app.my_team.items.get(i))
+            .and_then(|r| r.event_id)
+            .map(types::Detail::Event)
+    }
+    else
+    {
+        match app.focused {
+            Panel::Running => selected(&app.curr_events.state)
+                .and_then(|i| app.curr_events.items.get(i))
+                .and_then(|e| e.id)
+                .map(types::Detail::Event),
+            Panel::Main if app.main_view == types::MainView::Upcoming => selected(&app.upcoming.state)
+                .and_then(|i| app.upcoming.items.get(i))
+                .and_then(|e| e.id)
+                .map(types::Detail::Event),
+            Panel::Main if app.main_view == types::MainView::PastEvents => selected(&app.past_events_list.state)
+                .and_then(|i| app.past_events_list.items.get(i))
+                .and_then(|e| e.id)
+                .map(types::Detail::Event),
+            Panel::Leaderboard => selected(&app.leaderboard_stats.state)
+                .and_then(|i| app.leaderboard_stats.items.get(i))
+                .and_then(|t| t.team_id)
+                .map(types::Detail::Team),
+            // a followed event is opened when it is still listed somewhere
+            Panel::Watchlist => selected(&app.watchlist.state)
+                .and_then(|i| app.watchlist.items.get(i))
+                .and_then(|title| find_event_id(app, title))
+                .map(types::Detail::Event),
+            _ => None,
+        }
+    };
+
+    if app.tab == types::Tab::Dashboard
+    {
+        let tab = match app.focused {
+            Panel::Running | Panel::Main => types::Tab::Events,
+            Panel::Leaderboard => types::Tab::Leaderboard,
+            Panel::Writeups => types::Tab::Writeups,
+            Panel::Watchlist => types::Tab::Watchlist,
+        };
+        select_tab(app, tab, tx);
+    }
+    if let Some(detail) = detail
+    {
+        request_detail(app, detail, tx);
+        app.history.push(detail);
+    }
+}
+
+// ctftime id of a running or upcoming event by title
+fn find_event_id(app: &types::App, title: &str) -> Option<u32> {
+    let same = |t: &str| t.trim().eq_ignore_ascii_case(title.trim());
+    app.curr_events.items.iter()
+        .find(|e| same(&e.title))
+        .and_then(|e| e.id)
+        .or_else(|| app.upcoming.items.iter().find(|e| same(&e.title) && e.id.is_some()).and_then(|e| e.id))
+}
+
+// load whatever the detail view navigated back or forward to
+fn history_moved(app: &types::App, tx: &mpsc::Sender<types::Loaded>) {
+    if let Some(detail) = app.history.current
+    {
+        request_detail(app, detail, tx);
+    }
+}
+
+/*
+ * toggle_watch
+ * follow or unfollow the event selected in the focused panel
+ */
+fn toggle_watch(app: &mut types::App) {
+    let title = match app.focused {
+        Panel::Watchlist => app.watchlist.state.selected().and_then(|i| app.watchlist.items.get(i)).cloned(),
+        Panel::Running => app.curr_events.state.selected().and_then(|i| app.curr_events.items.get(i)).map(|e| e.title.clone()),
+        Panel::Main if app.main_view == types::MainView::Upcoming => {
+            app.upcoming.state.selected().and_then(|i| app.upcoming.items.get(i)).map(|e| e.title.clone())
+        }
+        Panel::Main => app.past_events_list.state.selected().and_then(|i| app.past_events_list.items.get(i)).map(|e| e.title.clone()),
+        Panel::Writeups => app.writeups.state.selected().and_then(|i| app.writeups.items.get(i)).map(|w| w.0.clone()),
+        _ => None,
+    };
+    if let Some(title) = title
+    {
+        app.toggle_watch(&title);
+    }
+}
+
+/*
+ * spawn_refresh
+ * periodically refetch the running feed, upcoming events and newest writeups
+ * so new events and writeups can be announced
+ */
+fn spawn_refresh(refresh_secs: u64, sources: config::SourcesConfig, tx: mpsc::Sender<types::Loaded>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(refresh_secs.max(30)));
+        loop {
+            interval.tick().await;
+            let mut upcoming = vec![];
+            let mut writeups = vec![];
+            let mut running = vec![];
+            let (running_res, upcoming_res, writeups_res) = tokio::join!(
+                loaders::load_current_events(&mut running, &sources),
+                loaders::load_upcoming_events(&mut upcoming, 14, &sources),
+                loaders::load_writeups(&mut writeups, 1, &sources),
+            );
+            let refresh = types::Loaded::Refresh {
+                running: running_res.map(|_| running),
+                upcoming: upcoming_res.map(|_| upcoming),
+                writeups: writeups_res.map(|_| writeups),
+            };
+            if tx.send(refresh).is_err()
+            {
+                break;
+            }
+        }
+    })
+}
+
+/*
+ * handle_key
+ * apply a single key press from the input thread to the app
+ */
+fn handle_key(app: &mut types::App, key: KeyCode, tx: &mpsc::Sender<types::Loaded>) {
+    match key {
+        // the help overlay swallows keys until closed
+        KeyCode::Char('?') => app.show_help = !app.show_help,
+        KeyCode::Esc if app.show_help => app.show_help = false,
+        KeyCode::Esc => app.error = None,
+        _ if app.show_help => (),
+        // only what the help lists for this tab and panel
+        _ if !keys::bound(app, key) => (),
+        KeyCode::Char('s') => cycle_focus(app, -1),
+        KeyCode::Char('w') => cycle_focus(app, 1),
+        // maximize the focused panel, or go back to the dashboard
+        KeyCode::Char('z') if app.tab == types::Tab::Dashboard => app.maximized = !app.maximized,
+        // tabs
+        KeyCode::Tab => {
+            let i = types::Tab::ALL.iter().position(|t| *t == app.tab).unwrap_or(0);
+            select_tab(app, types::Tab::ALL[(i + 1) % types::Tab::ALL.len()], tx);
+        }
+        KeyCode::BackTab => {
+            let i = types::Tab::ALL.iter().position(|t| *t == app.tab).unwrap_or(0);
+            select_tab(app, types::Tab::ALL[(i + types::Tab::ALL.len() - 1) % types::Tab::ALL.len()], tx);
+        }
+        KeyCode::Char(c @ '1'..='6') => {
+            let i = c as usize - '1' as usize;
+            select_tab(app, types::Tab::ALL[i], tx);
+        }
+        // detail navigation
+        KeyCode::Enter => open_detail(app, tx),
+        KeyCode::Char('b') => {
+            app.history.back();
+            history_moved(app, tx);
+        }
+        KeyCode::Char('n') => {
+            app.history.forward();
+            history_moved(app, tx);
+        }
+        KeyCode::Char('d') if app.tab == types::Tab::MyTeam => app.my_team.move_down(),
+        KeyCode::Char('a') if app.tab == types::Tab::MyTeam => app.my_team.move_up(),
+        KeyCode::Char('d') => {
+            if app.focused == Panel::Watchlist
+            {
+               app.watchlist.move_down();
+            }
+            else if app.focused == Panel::Running
+            {
+               app.curr_events.move_down();
+            }
+            else if app.focused == Panel::Main && app.main_view == types::MainView::Upcoming
+            {
+               app.upcoming.move_down();
+            }
+            else if app.focused == Panel::Main
+            {
+               app.past_events_list.move_down();
+            }
+            else if app.focused == Panel::Writeups
+            {
+               app.writeups.move_down(); 
+            }
+            else if app.focused == Panel::Leaderboard
+            {
+               app.leaderboard_stats.move_down(); 
+            }
+        }
+        KeyCode::Char('a') => {
+            if app.focused == Panel::Watchlist
+            {
+               app.watchlist.move_up();
+            }
+            else if app.focused == Panel::Running
+            {
+               app.curr_events.move_up();
+            }
+            else if app.focused == Panel::Main && app.main_view == types::MainView::Upcoming
+            {
+               app.upcoming.move_up();
+            }
+            else if app.focused == Panel::Main
+            {
+               app.past_events_list.move_up();
+            }
+            else if app.focused == Panel::Writeups
+            {
+               app.writeups.move_up(); 
+            }
+            else if app.focused == Panel::Leaderboard
+            {
+                app.leaderboard_stats.move_up();
+            }
+        }
+        // leaderboard year / view selection
+        // between ctftime's first rating year and this one
+        KeyCode::Char('[') | KeyCode::Char(']') => {
+            let by = if key == KeyCode::Char('[') { -1 } else { 1 };
+            if app.step_leaderboard_year(by)
+            {
+                leaderboard_changed(app, tx);
+            }
+        }
+        // rating charts
+        KeyCode::Char('c') if app.focused == Panel::Leaderboard => toggle_chart_team(app, tx),
+        KeyCode::Char('v') => {
+            app.main_view = match app.main_view {
+                types::MainView::PastEvents => types::MainView::Charts,
+                _ => types::MainView::PastEvents,
+            };
+        }
+        KeyCode::Char('u') => {
+            app.main_view = match app.main_view {
+                types::MainView::Upcoming => types::MainView::PastEvents,
+                _ => types::MainView::Upcoming,
+            };
+        }
+        // weekly schedule of the events marked with 'r'
+        KeyCode::Char('p') => {
+            app.main_view = match app.main_view {
+                types::MainView::Schedule => types::MainView::Upcoming,
+                _ => types::MainView::Schedule,
+            };
+        }
+        KeyCode::Char(',') if app.main_view == types::MainView::Schedule => app.week_offset -= 1,
+        KeyCode::Char('.') if app.main_view == types::MainView::Schedule => app.week_offset += 1,
+        KeyCode::Char('r') if app.focused == Panel::Main && app.main_view == types::MainView::Upcoming => {
+            if let Some(evt) = app.upcoming.state.selected().and_then(|i| app.upcoming.items.get(i))
+            {
+                app.registrations.cycle(evt);
+                if let Err(e) = app.registrations.save()
+                {
+                    app.error = Some(format!("registrations: {}", e));
+                }
+            }
+        }
+        // compact ticker for the running events
+        KeyCode::Char('t') => {
+            app.config.running.ticker = !app.config.running.ticker;
+            app.save_state();
+        }
+        // follow the selected event for notifications
+        KeyCode::Char('f') => toggle_watch(app),
+        // head-to-head comparison
+        KeyCode::Char('x') if app.focused == Panel::Leaderboard => pick_compare_team(app, tx),
+        KeyCode::Char('g') => {
+            app.config.leaderboard.view = match app.config.leaderboard.view {
+                config::LeaderboardView::Global => config::LeaderboardView::Country,
+                config::LeaderboardView::Country => config::LeaderboardView::Global,
+            };
+            leaderboard_changed(app, tx);
+        }
+        // biggest movers first, or back to rank order
+        KeyCode::Char('m') => {
+            app.leaderboard_sort = if app.leaderboard_sort.column == types::TeamColumn::Movers {
+                types::Sort { column: types::TeamColumn::Rank, descending: false }
+            } else {
+                types::Sort { column: types::TeamColumn::Movers, descending: true }
+            };
+            app.sort_leaderboard();
+        }
+        // only the selected team's country, or every team again
+        KeyCode::Char('l') => {
+            let code = match app.country_filter {
+                Some(_) => None,
+                None => app.leaderboard_stats.state.selected()
+                    .and_then(|i| app.leaderboard_stats.items.get(i))
+                    .and_then(|s| s.country_code.clone()),
+            };
+            app.filter_country(code);
+        }
+        // academic teams, non-academic teams or every team
+        KeyCode::Char('e') => {
+            app.academic_filter = app.academic_filter.next();
+            app.filter_leaderboard();
+            request_academic(app, tx);
+        }
+        // our hypothetical placement in the upcoming event opened
+        KeyCode::Char('+') if keys::what_if(app) => app.what_if.place_by(-1),
+        KeyCode::Char('-') if keys::what_if(app) => app.what_if.place_by(1),
+        KeyCode::Char('<') if keys::what_if(app) => app.what_if.share_by(-5),
+        KeyCode::Char('>') if keys::what_if(app) => app.what_if.share_by(5),
+        // cycle the sort column and direction of the focused list
+        KeyCode::Char('o') if app.tab != types::Tab::MyTeam && app.focused == Panel::Leaderboard => {
+            app.leaderboard_sort = app.leaderboard_sort.next(&types::TeamColumn::ALL);
+            app.sort_leaderboard();
+        }
+        KeyCode::Char('o') if app.tab != types::Tab::MyTeam && app.focused == Panel::Main && app.main_view == types::MainView::PastEvents => {
+            app.past_events_sort = app.past_events_sort.next(&types::EventColumn::ALL);
+            app.sort_past_events();
+        }
+        _ => (),
+    }
+}
+
+async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut types::App, quit: Arc<AtomicBool>) -> io::Result<()> {
+	let input_thread_quit = quit.clone();
+
+    // key presses and resizes are forwarded from the input thread over this channel
+    let (key_tx, key_rx) = mpsc::channel::<Event>();
+
+
+	let mut scroll_counter = 0;
+	let scroll_timer = 100;
 
 	// create input handler thread
-    let inputThreadHandle = std::thread::spawn(move || {
-		while *inputThreadRunning.lock().unwrap() < 1 {
-			if let Event::Key(key) = event::read().unwrap() {
-				if let KeyCode::Char('q') = key.code {
-					*inputThreadRunning.lock().unwrap() = 1;
-				}
-				else if let KeyCode::Char('w') = key.code {
-					*inputThreadUp.lock().unwrap() = 1;
-				}
-				else if let KeyCode::Char('s') = key.code {
-					*inputThreadDown.lock().unwrap() = 1;
-				}
-				else if let KeyCode::Char('d') = key.code {
-					*inputThreadRight.lock().unwrap() = 1;
-				}
-				else if let KeyCode::Char('a') = key.code {
-					*inputThreadLeft.lock().unwrap() = 1;
+	// polls instead of blocking in event::read so it notices `quit` and can be joined
+    let input_thread_handle = std::thread::spawn(move || {
+		while !input_thread_quit.load(Ordering::SeqCst) {
+			match event::poll(Duration::from_millis(100)) {
+				Ok(true) => (),
+				Ok(false) => continue,
+				Err(_) => break,
+			}
+			let evt = match event::read() {
+				Ok(evt) => evt,
+				Err(_) => break,
+			};
+			match evt {
+				// raw mode turns ctrl+c into a key press instead of SIGINT
+				Event::Key(key) if key.code == KeyCode::Char('q')
+					|| (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL)) => {
+					input_thread_quit.store(true, Ordering::SeqCst);
 				}
+				evt @ (Event::Key(_) | Event::Resize(_, _)) if key_tx.send(evt).is_err() => break,
+				_ => (),
 			}
 		}
+		input_thread_quit.store(true, Ordering::SeqCst);
+    });
+
+    // background fetches report back over this channel, nothing is awaited here
+    // so 'q' and signals work while ctftime is slow or unreachable.
+    // the refresh ticks right away and brings in the running feed,
+    // the first pages of past events and writeups come through request_pages
+    let (loaded_tx, loaded_rx) = mpsc::channel::<types::Loaded>();
+    let refresh_task = spawn_refresh(app.config.notifications.refresh_secs, app.config.sources.clone(), loaded_tx.clone());
+    request_leaderboard(app, &loaded_tx);
+    // the leaderboard is refetched as often as the feeds for fresh rank changes
+    let leaderboard_every = std::time::Duration::from_secs(app.config.notifications.refresh_secs.max(30));
+    let mut leaderboard_at = std::time::Instant::now();
+
+    // log in with what `ctfterm login` saved, the account panels need the session
+    if let Some(credentials) = session::load(&app.config.account)
+    {
+        let tx = loaded_tx.clone();
+        tokio::spawn(async move {
+            let _ = tx.send(types::Loaded::Login(loaders::load_login(credentials).await));
+        });
+    }
+
+    let mut res = Ok(());
+    while !quit.load(Ordering::SeqCst) {
+        // handle inputs
+        while let Ok(evt) = key_rx.try_recv()
+        {
+            match evt {
+                Event::Key(key) => handle_key(app, key.code, &loaded_tx),
+                // redraw everything at the new size right away
+                Event::Resize(width, height) => {
+                    if let Err(e) = terminal.resize(Rect::new(0, 0, width, height))
+                    {
+                        res = Err(e);
+                        quit.store(true, Ordering::SeqCst);
+                    }
+                }
+                _ => (),
+            }
+        }
+        for panic in term::take_panics()
+        {
+            app.error = Some(panic);
+        }
+
+		// handle ticker auto-scrolling
+		if app.config.running.ticker
+		{
+			// check timer, for artificial sleep
+			// using sleep would get funky with input
+			if scroll_counter <= 0
+			{
+				scroll_counter = scroll_timer;
+
+                // scroll current event string
+                app.ticker_offset = app.ticker_offset.wrapping_add(1);
+			}
+			else
+			{
+				scroll_counter -= 1;
+			}
+		}
+
+		// update terminal view
+        if let Err(e) = terminal.draw(|f| ui(f, app))
+        {
+            res = Err(e);
+            break;
+        }
+	    
+        // pick up finished background fetches and lazily
+        // pull in more pages as the selection nears the end
+        while let Ok(loaded) = loaded_rx.try_recv()
+        {
+            let logged_in = matches!(loaded, types::Loaded::Login(Ok(_)));
+            let leaderboard = matches!(loaded, types::Loaded::Leaderboard { res: Ok(_), .. });
+            apply_loaded(app, loaded);
+            if logged_in && app.tab == types::Tab::MyTeam
+            {
+                request_account(app, &loaded_tx);
+            }
+            if leaderboard
+            {
+                request_academic(app, &loaded_tx);
+            }
+        }
+        request_pages(app, &loaded_tx);
+        if leaderboard_at.elapsed() >= leaderboard_every
+        {
+            request_leaderboard(app, &loaded_tx);
+            leaderboard_at = std::time::Instant::now();
+        }
+        check_reminders(app);
+    }
+
+    // stop the input thread and the periodic refresh, one-shot fetches
+    // still in flight are dropped with the runtime
+    quit.store(true, Ordering::SeqCst);
+    refresh_task.abort();
+    let _ = input_thread_handle.join();
+    res
+}
+
+
+/*
+ * build_leaderboard
+ * create leaderboard widget via App.leaderboard_stats 
+ */
+fn build_leaderboard<'a>(app: &'a types::App, widths: &'a [Constraint]) -> Table<'a> {
+
+    // create surrounding block
+    let block = Block::default()
+        .title(Span::styled(
+            app.leaderboard_title(),
+            app.theme.title(),
+        ))
+        .borders(Borders::ALL)
+        .border_style(app.theme.border(app.focused == Panel::Leaderboard))
+        .title_alignment(Alignment::Left);
+    
+    // set up headers
+    // the sorted column carries an arrow
+    let sort = app.leaderboard_sort;
+    let sorted = |label: &'static str, column: types::TeamColumn| {
+        if sort.column == column { format!("{}{}", label, sort.arrow()) } else { label.to_string() }
+    };
+    let header_cells = [
+        sorted("Rank", types::TeamColumn::Rank),
+        sorted("+/-", types::TeamColumn::Movers),
+        sorted("Team", types::TeamColumn::Name),
+        sorted("Points", types::TeamColumn::Points),
+        String::from("+/-"),
+        sorted("Country", types::TeamColumn::Country),
+    ]
+        .into_iter()
+        .map(|h| Cell::from(h).style(app.theme.header()));
+    let header = Row::new(header_cells)
+        .style(Style::default().fg(app.theme.border))
+        .height(1);
+
+    let len = |i: usize| match widths.get(i) {
+        Some(Constraint::Length(n)) => *n as usize,
+        _ => usize::MAX,
+    };
+    let (team_width, country_width) = (len(2), len(5));
+
+    // create rows of leaderboard data
+    let rows = app.leaderboard_stats.items.iter().map(|stat| {
+        let height = 1;
+        // mark teams that are plotted in the chart view
+        let charted = app.chart_teams.iter().any(|t| Some(t.id) == stat.team_id);
+        let picked = app.compare_pick.is_some() && app.compare_pick == stat.team_id;
+        let name = if picked {
+            format!("vs {}", stat.name)
+        } else if charted {
+            format!("* {}", stat.name)
+        } else {
+            stat.name.clone()
+        };
+        // change since the previous snapshot: ▲3 / ▼1 and the points won or lost
+        let (places, points) = match types::App::movement(stat, &app.previous_standings) {
+            Some(types::Movement::New) => (
+                Span::styled("new", Style::default().fg(app.theme.positive)),
+                Span::raw(""),
+            ),
+            Some(types::Movement::Moved { places, points }) => {
+                let color = |better: bool| Style::default().fg(if better { app.theme.positive } else { app.theme.negative });
+                let places = match places {
+                    0 => Span::raw("="),
+                    p if p > 0 => Span::styled(format!("▲{}", p), color(true)),
+                    p => Span::styled(format!("▼{}", -p), color(false)),
+                };
+                let points = if points.abs() < 0.0005 {
+                    Span::raw("")
+                } else {
+                    Span::styled(format!("{:+.2}", points), color(points > 0.0))
+                };
+                (places, points)
+            }
+            None => (Span::raw(""), Span::raw("")),
+        };
+        let cells = vec![
+            Cell::from( ellipsize(&stat.rank, len(0)) ),
+            Cell::from( places ),
+            Cell::from( ellipsize(&name, team_width) ),
+            Cell::from( ellipsize(&stat.points, len(3)) ),
+            Cell::from( points ),
+            Cell::from( ellipsize(&country(stat, country_width, app.config.leaderboard.flags), country_width) ),
+        ];
+        Row::new(cells).height(height as u16)
+    });
+
+    // render data into table
+    let t = Table::new(rows)
+        .header(header)
+        .block(block)
+        .highlight_style(app.theme.highlight())
+        .highlight_symbol(">> ")
+        .widths(widths);
+    t
+}
+
+// the country name, or just the code or flag in a narrow column
+fn country(stat: &types::TeamStat, width: usize, flags: bool) -> String {
+    match &stat.country_code {
+        Some(code) if width < 8 && flags => countries::flag(code).unwrap_or_else(|| code.clone()),
+        Some(code) if width < 8 => code.clone(),
+        Some(code) => countries::describe(code, flags),
+        None => stat.country.clone(),
+    }
+}
+
+/*
+ * leaderboard_widths
+ * fixed rank, movement and points columns, the team name gets what is left;
+ * the country is dropped first on narrow panels, then the points change
+ */
+fn leaderboard_widths(width: usize) -> [Constraint; 6] {
+    // five spaces between the columns
+    let inner = list_width(width).saturating_sub(5);
+    let country_width = if inner >= 68 { 16 } else if inner >= 48 { 4 } else { 0 };
+    let change_width = if inner >= 40 { 8 } else { 0 };
+    let team_width = inner.saturating_sub(5 + 4 + 9 + change_width + country_width);
+    [
+        Constraint::Length(5),
+        Constraint::Length(4),
+        Constraint::Length(team_width as u16),
+        Constraint::Length(9),
+        Constraint::Length(change_width as u16),
+        Constraint::Length(country_width as u16),
+    ]
+}
+
+// room for text in a bordered list with a ">> " highlight symbol
+fn list_width(width: usize) -> usize {
+    width.saturating_sub(2 + 3)
+}
+
+// cut a string to `width` characters, marking the cut with an ellipsis
+fn ellipsize(s: &str, width: usize) -> String {
+    if s.chars().count() <= width
+    {
+        return s.to_string();
+    }
+    if width == 0
+    {
+        return String::new();
+    }
+    let mut cut: String = s.chars().take(width - 1).collect();
+    cut.push('…');
+    cut
+}
+
+// text progress bar such as "[#####-----]" that fits `width` columns
+fn progress_bar(progress: f64, width: usize) -> String {
+    let inner = width.saturating_sub(2);
+    let done = ((inner as f64) * progress).round() as usize;
+    format!("[{}{}]", "#".repeat(done), "-".repeat(inner - done.min(inner)))
+}
+
+fn running_block(app: &types::App) -> Block<'_> {
+
+    Block::default()
+        .borders(Borders::ALL)
+        .border_style(app.theme.border(app.focused == Panel::Running))
+        .title(Span::styled(
+            "Now Running",
+            app.theme.title(),
+        ))
+        .title_alignment(Alignment::Right)
+}
+
+fn build_current_events<'a>(app: &'a types::App, width: usize) -> List<'a> {
+    let now = chrono::Utc::now();
+    // Iterate through all running events, one title and one time line each
+    let items: Vec<ListItem> = app
+        .curr_events
+        .items
+        .iter()
+        .map(|evt| {
+            let mark = if app.is_watched(&evt.title) { "* " } else { "" };
+            let mut timing = match evt.remaining(now) {
+                Some(left) => format!("ends in {} ", format_duration(left)),
+                None => String::new(),
+            };
+            if let Some(progress) = evt.progress(now)
+            {
+                let bar_width = width.saturating_sub(timing.len() + 12).min(30);
+                timing.push_str(&format!("{} {:.0}%", progress_bar(progress, bar_width), progress * 100.0));
+            }
+            let mut details = format!("{} | weight {:.2}", evt.format, evt.weight);
+            if !evt.organizers.is_empty()
+            {
+                details.push_str(&format!(" | by {}", evt.organizers.join(", ")));
+            }
+            let text_width = list_width(width);
+            ListItem::new(vec![
+                Spans::from(vec![Span::styled(ellipsize(&format!("{}{}", mark, evt.title), text_width), Style::default().add_modifier(Modifier::BOLD))]),
+                Spans::from(vec![Span::raw(timing)]),
+                Spans::from(vec![Span::raw(ellipsize(&details, text_width))]),
+            ])
+        })
+        .collect();
+
+    // Create a List from all list items and highlight the currently selected one
+    List::new(items)
+        .block(running_block(app))
+        .highlight_style(app.theme.highlight())
+        .highlight_symbol(">> ")
+}
+
+// the running titles as one marquee line, scrolled by app.ticker_offset
+fn build_ticker(app: &types::App, width: usize) -> Paragraph<'_> {
+    let text: Vec<char> = app.ticker_text().chars().collect();
+    let line: String = text.iter()
+        .cycle()
+        .skip(app.ticker_offset % text.len())
+        .take(width.min(text.len()))
+        .collect();
+    Paragraph::new(Span::styled(line, Style::default().add_modifier(Modifier::BOLD)))
+        .block(running_block(app))
+}
+
+fn build_past_events<'a>(app: &'a types::App, width: usize) -> List<'a> {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(app.theme.border(app.focused == Panel::Main))
+        .title(Span::styled(
+            format!("Past Events [{}{}]", app.past_events_sort.column.label(), app.past_events_sort.arrow()),
+            app.theme.title(),
+        ))
+        .title_alignment(Alignment::Right);
+    // Iterate through all elements in the `items` app and append some debug text to it.
+    let items: Vec<ListItem> = app
+        .past_events_list
+        .items
+        .iter()
+        .map(|evt| {
+            let mut date = match (evt.start, evt.finish) {
+                (Some(start), Some(finish)) => app.clock.range(start, finish),
+                _ => evt.date.clone(),
+            };
+            if let Some(weight) = evt.weight
+            {
+                date.push_str(&format!(" · weight {:.2}", weight));
+            }
+            if let Some(teams) = evt.participants
+            {
+                date.push_str(&format!(" · {} teams", teams));
+            }
+            ListItem::new(vec![
+                Spans::from("-".repeat(width)),
+                Spans::from(vec![Span::styled(ellipsize(&evt.title, list_width(width)), Style::default().add_modifier(Modifier::BOLD))]),
+                Spans::from(vec![Span::raw(ellipsize(&date, list_width(width)))]),
+            ])
+        })
+        .collect();
+
+    // Create a List from all list items and highlight the currently selected one
+    let items = List::new(items)
+        .block(block)
+        .highlight_style(app.theme.highlight())
+        .highlight_symbol(">> ");
+    items
+}
+
+fn build_writeups(app: &types::App, width: usize) -> List<'_> {
+    // NOTE: each entry has a height 6.5
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(app.theme.border(app.focused == Panel::Writeups))
+        .title(Span::styled(
+            "Write Ups",
+            app.theme.title(),
+        ))
+        .title_alignment(Alignment::Right);
+    // Iterate through all elements in the `items` app and append some debug text to it.
+    let items: Vec<ListItem> = app
+        .writeups
+        .items
+        .iter()
+        .map(|(_, w2, w3, _, _)| {
+            ListItem::new(vec![
+                Spans::from(vec![
+                            Span::raw(ellipsize(&format!("[{}]{}", w3, w2), list_width(width))),]),
+            ])
+        })
+        .collect();
+
+    // Create a List from all list items and highlight the currently selected one
+    let items = List::new(items)
+        .block(block)
+        .highlight_style(app.theme.highlight())
+        .highlight_symbol(">> ");
+    items
+}
+
+/*
+ * render_schedule
+ * one week of marked and unmarked upcoming events with the hours
+ * committed and the events we play that run at the same time
+ */
+fn render_schedule<B: Backend>(f: &mut Frame<B>, app: &types::App, area: Rect) {
+    let from = schedule::week_start(app.week_offset);
+    let to = from + chrono::Duration::weeks(1);
+    let slots = schedule::week(&app.registrations, &app.upcoming.items, from);
+
+    let hours = |plan: schedule::Plan| -> f64 {
+        slots.iter().filter(|s| s.plan == Some(plan)).fold(0.0, |sum, s| sum + s.hours_in(from, to))
+    };
+    let conflicts = slots.iter().filter(|s| s.conflict).count();
+    let block = Block::default()
+        .title(Span::styled(
+            format!("Schedule {} - {}", from.format("%b %d"), (to - chrono::Duration::days(1)).format("%b %d")),
+            app.theme.title(),
+        ))
+        .borders(Borders::ALL)
+        .border_style(app.theme.border(app.focused == Panel::Main))
+        .title_alignment(Alignment::Right);
+
+    let mut lines = vec![
+        Spans::from(vec![
+            Span::styled(format!("{:.0}h playing", hours(schedule::Plan::Playing)), Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(format!(", {:.0}h maybe", hours(schedule::Plan::Maybe))),
+            if conflicts > 0
+            {
+                Span::styled(format!(", {} conflicting", conflicts), Style::default().fg(app.theme.negative).add_modifier(Modifier::BOLD))
+            }
+            else
+            {
+                Span::raw("")
+            },
+        ]),
+        Spans::from(Span::styled("',' / '.' previous / next week, 'r' on an upcoming event marks it", Style::default().add_modifier(Modifier::ITALIC))),
+    ];
+    for slot in &slots
+    {
+        let plan = slot.plan.map(|p| p.label()).unwrap_or("-");
+        let style = match slot.plan {
+            _ if slot.conflict => Style::default().fg(app.theme.negative),
+            Some(schedule::Plan::Playing) => Style::default().fg(app.theme.positive).add_modifier(Modifier::BOLD),
+            Some(_) => Style::default(),
+            None => Style::default().add_modifier(Modifier::DIM),
+        };
+        lines.push(Spans::from(""));
+        lines.push(Spans::from(Span::styled(format!("[{}] {}", plan, slot.title), style)));
+        lines.push(Spans::from(format!("  {}", app.clock.range(slot.start, slot.finish))));
+        if !slot.overlaps.is_empty()
+        {
+            let what = if slot.conflict { "conflicts with" } else { "overlaps" };
+            lines.push(Spans::from(Span::styled(format!("  {} {}", what, slot.overlaps.join(", ")), style)));
+        }
+    }
+    if slots.is_empty()
+    {
+        lines.push(Spans::from(""));
+        lines.push(Spans::from("Nothing planned this week"));
+    }
+
+    f.render_widget(Paragraph::new(lines).block(block).wrap(Wrap { trim: false }), area);
+}
+
+/*
+ * build_chart
+ * line chart of one series per charted team, the x axis is labelled with `x_labels`
+ */
+fn build_chart<'a>(theme: &theme::Theme, title: &'a str, y_title: &'a str, series: &'a [(String, Vec<(f64, f64)>)], x_labels: Vec<String>) -> Chart<'a> {
+    let points = series.iter().flat_map(|(_, data)| data.iter());
+    let (x_max, y_max) = points.fold((1.0_f64, 1.0_f64), |(x, y), p| (x.max(p.0), y.max(p.1)));
+    let x_min = series.iter().flat_map(|(_, data)| data.iter()).map(|p| p.0).fold(x_max, f64::min);
+
+    let datasets = series.iter().enumerate().map(|(i, (name, data))| {
+        Dataset::default()
+            .name(name.as_str())
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(theme.series(i)))
+            .data(data)
+    }).collect();
+
+    Chart::new(datasets)
+        .block(
+            Block::default()
+                .title(Span::styled(title, theme.title()))
+                .borders(Borders::ALL)
+                .border_style(theme.border(false)),
+        )
+        .x_axis(
+            Axis::default()
+                .bounds([x_min, x_max])
+                .labels(x_labels.into_iter().map(Span::raw).collect()),
+        )
+        .y_axis(
+            Axis::default()
+                .title(y_title)
+                .bounds([0.0, y_max * 1.1])
+                .labels(vec![Span::raw("0"), Span::raw(format!("{:.0}", y_max))]),
+        )
+}
+
+/*
+ * render_charts
+ * rating points and global rank by year, and per-event points this season,
+ * for every team picked from the leaderboard
+ */
+fn render_charts<B: Backend>(f: &mut Frame<B>, app: &types::App, area: Rect) {
+    if app.chart_teams.is_empty()
+    {
+        let hint = Paragraph::new("Select a team in the leaderboard and press 'c' to chart it (up to 5)")
+            .block(Block::default().title(Span::styled("Rating History", app.theme.title())).borders(Borders::ALL).border_style(app.theme.border(false)));
+        f.render_widget(hint, area);
+        return;
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(34), Constraint::Percentage(33), Constraint::Percentage(33)].as_ref())
+        .split(area);
+
+    let rating: Vec<(String, Vec<(f64, f64)>)> = app.chart_teams.iter()
+        .map(|t| (t.name.clone(), t.years.iter().map(|y| (y.0 as f64, y.1)).collect()))
+        .collect();
+    let rank: Vec<(String, Vec<(f64, f64)>)> = app.chart_teams.iter()
+        .map(|t| (t.name.clone(), t.years.iter().filter_map(|y| Some((y.0 as f64, y.2? as f64))).collect()))
+        .collect();
+    let current_year = chrono::Datelike::year(&chrono::Utc::now());
+    let season: Vec<(String, Vec<(f64, f64)>)> = app.chart_teams.iter()
+        .map(|t| (t.name.clone(), t.season(current_year).iter().enumerate().map(|(i, r)| ((i + 1) as f64, r.rating_points)).collect()))
+        .collect();
+
+    let years: Vec<i32> = app.chart_teams.iter().flat_map(|t| t.years.iter().map(|y| y.0)).collect();
+    let year_labels = match (years.iter().min(), years.iter().max()) {
+        (Some(first), Some(last)) => vec![first.to_string(), last.to_string()],
+        _ => vec![],
+    };
+    // label the season axis with the first and last event of the longest season
+    let longest = app.chart_teams.iter().map(|t| t.season(current_year)).max_by_key(|s| s.len()).unwrap_or_default();
+    let event_labels = match (longest.first(), longest.last()) {
+        (Some(first), Some(last)) => vec![first.event.clone(), last.event.clone()],
+        _ => vec![],
+    };
+
+    f.render_widget(build_chart(&app.theme, "Rating Points", "points", &rating, year_labels.clone()), chunks[0]);
+    f.render_widget(build_chart(&app.theme, "Global Rank", "rank", &rank, year_labels), chunks[1]);
+    f.render_widget(build_chart(&app.theme, "Season Event Points", "points", &season, event_labels), chunks[2]);
+}
+
+fn build_upcoming<'a>(app: &'a types::App, width: usize) -> List<'a> {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(app.theme.border(app.focused == Panel::Main))
+        .title(Span::styled(
+            "Upcoming Events",
+            app.theme.title(),
+        ))
+        .title_alignment(Alignment::Right);
+    let items: Vec<ListItem> = app
+        .upcoming
+        .items
+        .iter()
+        .map(|evt| {
+            let mark = if app.is_watched(&evt.title) { "* " } else { "" };
+            let plan = app.registrations.plan(evt).map(|p| format!("[{}] ", p.label())).unwrap_or_default();
+            ListItem::new(vec![
+                Spans::from("-".repeat(width)),
+                Spans::from(vec![Span::styled(ellipsize(&format!("{}{}{}", mark, plan, evt.title), list_width(width)), Style::default().add_modifier(Modifier::BOLD))]),
+                Spans::from(vec![Span::raw(ellipsize(&format!(
+                    "{} | {} | weight {:.2}{}",
+                    app.clock.range(evt.start, evt.finish),
+                    evt.format,
+                    evt.weight,
+                    if evt.onsite { " | onsite" } else { "" },
+                ), list_width(width)))]),
+            ])
+        })
+        .collect();
+
+    List::new(items)
+        .block(block)
+        .highlight_style(app.theme.highlight())
+        .highlight_symbol(">> ")
+}
+
+fn build_watchlist(app: &types::App) -> List<'_> {
+    let block = Block::default()
+        .title(Span::styled(
+            "Write Up Watchlist",
+            app.theme.title(),
+        ))
+        .border_style(app.theme.border(app.focused == Panel::Watchlist))
+        .borders(Borders::ALL);
+    let items: Vec<ListItem> = app
+        .watchlist
+        .items
+        .iter()
+        .map(|title| ListItem::new(title.as_str()))
+        .collect();
+
+    List::new(items)
+        .block(block)
+        .highlight_style(app.theme.highlight())
+        .highlight_symbol(">> ")
+}
+
+/*
+ * render_toasts
+ * stack pending notifications in the top right corner
+ */
+fn render_toasts<B: Backend>(f: &mut Frame<B>, app: &types::App) {
+    let size = f.size();
+    let width = (size.width / 3).max(30).min(size.width);
+    let mut y = 1;
+    for toast in &app.toasts
+    {
+        if y + 3 > size.height
+        {
+            break;
+        }
+        let area = Rect::new(size.width - width, y, width, 3);
+        let toast = Paragraph::new(toast.message.as_str())
+            .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(app.theme.toast)));
+        f.render_widget(Clear, area);
+        f.render_widget(toast, area);
+        y += 3;
+    }
+}
+
+// describe a team's country and academic status for the comparison header
+fn team_summary(team: &types::TeamHistory) -> String {
+    let country = if team.country.is_empty() { String::from("-") } else { countries::describe(&team.country, false) };
+    let kind = if team.academic { "academic" } else { "non-academic" };
+    format!("{} ({}, {})", team.name, country, kind)
+}
+
+/*
+ * render_comparison
+ * head-to-head view of the two teams picked with 'x' in the leaderboard
+ */
+fn render_comparison<B: Backend>(f: &mut Frame<B>, app: &types::App, area: Rect) {
+    let block = Block::default()
+        .title(Span::styled(
+            "Head To Head",
+            app.theme.title(),
+        ))
+        .borders(Borders::ALL)
+        .border_style(app.theme.border(false));
+
+    let cmp = match &app.comparison {
+        Some(cmp) => cmp,
+        None => {
+            let hint = Paragraph::new("Press 'x' on two leaderboard teams to compare them").block(block);
+            f.render_widget(hint, area);
+            return;
+        }
+    };
+
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Percentage(40), Constraint::Min(0)].as_ref())
+        .split(inner);
+
+    let header = Paragraph::new(vec![
+        Spans::from(vec![Span::styled(team_summary(&cmp.a), Style::default().fg(app.theme.series(0)))]),
+        Spans::from(vec![Span::styled(team_summary(&cmp.b), Style::default().fg(app.theme.series(1)))]),
+        Spans::from(format!(
+            "{} shared events, {} wins / {} losses for {}",
+            cmp.shared.len(), cmp.wins, cmp.losses, cmp.a.name
+        )),
+    ]);
+    f.render_widget(header, chunks[0]);
+
+    // rating history side by side, one row per year either team has a rating for
+    let mut years: Vec<i32> = cmp.a.years.iter().chain(cmp.b.years.iter()).map(|y| y.0).collect();
+    years.sort_unstable();
+    years.dedup();
+    let rating = |team: &types::TeamHistory, year: i32| match team.years.iter().find(|y| y.0 == year) {
+        Some((_, points, Some(place), _)) => format!("{:.2} (#{})", points, place),
+        Some((_, points, None, _)) => format!("{:.2}", points),
+        None => String::from("-"),
+    };
+    let rows = years.iter().rev().map(|year| {
+        Row::new(vec![year.to_string(), rating(&cmp.a, *year), rating(&cmp.b, *year)])
     });
+    let history = Table::new(rows)
+        .header(Row::new(vec!["Year", cmp.a.name.as_str(), cmp.b.name.as_str()]).style(app.theme.header()))
+        .widths(&[Constraint::Percentage(20), Constraint::Percentage(40), Constraint::Percentage(40)]);
+    f.render_widget(history, chunks[1]);
 
-	let mut running = 0;
-    while running < 1 {
-		// updated shared running var
-		running = *arc.lock().unwrap();
+    // placements in events both teams played, newest first
+    let rows = cmp.shared.iter().rev().map(|(event, a, b)| {
+        let style = if a < b { Style::default().fg(app.theme.positive) } else if a > b { Style::default().fg(app.theme.negative) } else { Style::default() };
+        Row::new(vec![event.clone(), a.to_string(), b.to_string()]).style(style)
+    });
+    let shared = Table::new(rows)
+        .header(Row::new(vec!["Shared Event", cmp.a.name.as_str(), cmp.b.name.as_str()]).style(app.theme.header()))
+        .widths(&[Constraint::Percentage(60), Constraint::Percentage(20), Constraint::Percentage(20)]);
+    f.render_widget(shared, chunks[2]);
+}
 
-        // handle inputs
-        if *down.lock().unwrap() > 0
-        {
-            if app.focused < 1
-            {
-                app.focused = 4;
-            }
-            else
-            {
-                app.focused -= 1;
-            }
-            *down.lock().unwrap() = 0;
+/*
+ * render_panel
+ * draw one dashboard panel into the given area
+ */
+fn render_panel<B: Backend>(f: &mut Frame<B>, app: &mut types::App, panel: Panel, area: Rect) {
+    match panel {
+        Panel::Leaderboard => {
+            let mut table_state = TableState::default();
+            table_state.select(app.leaderboard_stats.state.selected());
+            let widths = leaderboard_widths(area.width as usize);
+            f.render_stateful_widget(build_leaderboard(app, &widths), area, &mut table_state);
         }
-        else if *up.lock().unwrap() > 0
-        {
-            app.focused = (app.focused+1)%5;
-            *up.lock().unwrap() = 0;
+        Panel::Running if app.config.running.ticker => {
+            f.render_widget(build_ticker(app, area.width.saturating_sub(2) as usize), area);
         }
-        else if *right.lock().unwrap() > 0
-        {
-            if (app.focused == 3)
-            {
-               app.writeups.move_down(); 
-            }
-            else if (app.focused == 4)
-            {
-               app.leaderboard_stats.move_down(); 
-            }
-            *right.lock().unwrap() = 0;
+        Panel::Running => {
+            let mut state = app.curr_events.state.clone();
+            f.render_stateful_widget(build_current_events(app, area.width as usize), area, &mut state);
+            app.curr_events.state = state;
         }
-        else if *left.lock().unwrap() > 0
+        // past events, or the rating charts / comparison / upcoming events in their place
+        Panel::Main => match app.main_view
         {
-            if (app.focused == 3)
-            {
-               app.writeups.move_up(); 
+            types::MainView::Charts => render_charts(f, app, area),
+            types::MainView::Compare => render_comparison(f, app, area),
+            types::MainView::Schedule => render_schedule(f, app, area),
+            types::MainView::Upcoming => {
+                let mut state = app.upcoming.state.clone();
+                f.render_stateful_widget(build_upcoming(app, area.width as usize), area, &mut state);
+                app.upcoming.state = state;
             }
-            else if (app.focused == 4)
-            {
-                app.leaderboard_stats.move_up();
+            types::MainView::PastEvents => {
+                let mut state = app.past_events_list.state.clone();
+                f.render_stateful_widget(build_past_events(app, area.width as usize), area, &mut state);
+                app.past_events_list.state = state;
             }
-            *left.lock().unwrap() = 0;
+        },
+        Panel::Writeups => {
+            let mut state = app.writeups.state.clone();
+            f.render_stateful_widget(build_writeups(app, area.width as usize), area, &mut state);
+            app.writeups.state = state;
+        }
+        // events followed for notifications
+        Panel::Watchlist => {
+            let mut state = app.watchlist.state.clone();
+            f.render_stateful_widget(build_watchlist(app), area, &mut state);
+            app.watchlist.state = state;
         }
+    }
+}
 
-		// handle data auto-scrolling
-		if autoscroll
-		{
-			// check timer, for artificial sleep
-			// using sleep would get funky with input
-			if scrollCounter <= 0
-			{
-				scrollCounter = scrollTimer;
+// split an area by relative sizes
+fn split(area: Rect, direction: Direction, sizes: &[u16]) -> Vec<Rect> {
+    let total: u32 = sizes.iter().map(|s| (*s).max(1) as u32).sum();
+    let constraints: Vec<Constraint> = sizes.iter()
+        .map(|s| Constraint::Ratio((*s).max(1) as u32, total))
+        .collect();
+    Layout::default()
+        .direction(direction)
+        .constraints(constraints)
+        .split(area)
+}
 
-                // scroll current event string
-                if app.curr_events.items.len() > 0
-                {
-                    let mut curr_event_s = String::new();
-                    let mut first_ch = String::new();
+/*
+ * layout_areas
+ * place the visible panels of the configured layout, rows and columns
+ * left without a visible panel give their space to the others
+ */
+fn layout_areas(layout: &config::LayoutConfig, area: Rect) -> Vec<(Panel, Rect)> {
+    let shown = |p: &&config::PanelConfig| !layout.hidden.contains(&p.panel);
+    let column_shown = |c: &&config::ColumnConfig| c.panels.iter().any(|p| shown(&p));
+    let rows: Vec<&config::RowConfig> = layout.rows.iter()
+        .filter(|r| r.columns.iter().any(|c| column_shown(&c)))
+        .collect();
 
-                    curr_event_s = app.curr_events.get(0).clone();
+    let mut areas = vec![];
+    let heights: Vec<u16> = rows.iter().map(|r| r.height).collect();
+    for (row, row_area) in rows.iter().zip(split(area, Direction::Vertical, &heights))
+    {
+        let columns: Vec<&config::ColumnConfig> = row.columns.iter().filter(column_shown).collect();
+        let widths: Vec<u16> = columns.iter().map(|c| c.width).collect();
+        for (column, column_area) in columns.iter().zip(split(row_area, Direction::Horizontal, &widths))
+        {
+            let panels: Vec<&config::PanelConfig> = column.panels.iter().filter(shown).collect();
+            let heights: Vec<u16> = panels.iter().map(|p| p.height).collect();
+            for (p, panel_area) in panels.iter().zip(split(column_area, Direction::Vertical, &heights))
+            {
+                areas.push((p.panel, panel_area));
+            }
+        }
+    }
+    areas
+}
 
-                    first_ch.push(curr_event_s.chars().nth(0).unwrap());
+// "3m ago" style age of a refresh
+fn ago(when: chrono::DateTime<chrono::Utc>) -> String {
+    let secs = (chrono::Utc::now() - when).num_seconds().max(0);
+    if secs < 60 { format!("{}s ago", secs) } else { format!("{} ago", format_duration(chrono::Duration::seconds(secs))) }
+}
 
-                    curr_event_s = curr_event_s[1..curr_event_s.len()].to_string();
-                    curr_event_s.push_str(&first_ch);
+/*
+ * build_status
+ * bottom line: focused panel, network state, refresh ages, filters and the last error
+ */
+fn build_status(app: &types::App) -> Paragraph<'_> {
+    let sep = || Span::raw(" | ");
+    let focused = match app.tab {
+        types::Tab::MyTeam => "my team",
+        _ => match app.focused {
+            Panel::Watchlist => "watchlist",
+            Panel::Running => "running",
+            Panel::Main => match app.main_view {
+                types::MainView::PastEvents => "past events",
+                types::MainView::Upcoming => "upcoming",
+                types::MainView::Charts => "charts",
+                types::MainView::Compare => "comparison",
+                types::MainView::Schedule => "schedule",
+            },
+            Panel::Writeups => "writeups",
+            Panel::Leaderboard => "leaderboard",
+        },
+    };
+    let mut spans = vec![
+        Span::styled(format!(" {} ", focused), app.theme.highlight()),
+        sep(),
+        if app.offline()
+        {
+            Span::styled("offline", Style::default().fg(app.theme.negative))
+        }
+        else
+        {
+            Span::styled("online", Style::default().fg(app.theme.positive))
+        },
+    ];
 
-                    app.curr_events.set(curr_event_s, 0);
-                }
-			}
-			else
-			{
-				scrollCounter -= 1;
-			}
-		}
+    if let Some(user) = &app.user
+    {
+        spans.push(sep());
+        spans.push(Span::raw(format!("logged in as {}", user)));
+    }
 
-		// update terminal view
-        terminal.draw(|f| ui(f, app));
-	    
-        let tmp_needs_load = *needs_load.lock().unwrap();
-        if tmp_needs_load
+    for source in ["running", "upcoming", "past events", "writeups", "leaderboard"]
+    {
+        if let Some(when) = app.refreshed.get(source)
         {
-            if  *loaded.lock().unwrap() == load_total
-            {
-                let res = &*results_.lock().unwrap();
-                let new_items = &res;
-                app.curr_events.update( &new_items.0.as_ref().unwrap() );
-                app.leaderboard_stats.update( &new_items.1.as_ref().unwrap() );
-                app.past_events_list.update( &new_items.2.as_ref().unwrap() );
-                app.writeups.update( &new_items.3.as_ref().unwrap() );
+            spans.push(sep());
+            spans.push(Span::raw(format!("{} {}", source, ago(*when))));
+        }
+    }
 
-                *needs_load.lock().unwrap() = false;
-            }
+    let mut filters = vec![];
+    if app.config.leaderboard.view == config::LeaderboardView::Country
+    {
+        filters.push(format!("country {}", app.config.leaderboard.country));
+    }
+    if let Some(code) = &app.country_filter
+    {
+        filters.push(format!("{} teams only", code));
+    }
+    if let Some(label) = app.academic_filter.label()
+    {
+        filters.push(format!("{} teams", label));
+    }
+    let filter = &app.config.notifications.filter;
+    if filter.enabled
+    {
+        let mut desc = format!("weight >= {}", filter.min_weight);
+        if !filter.formats.is_empty()
+        {
+            desc.push_str(&format!(", {}", filter.formats.join("/")));
+        }
+        if filter.online_only
+        {
+            desc.push_str(", online");
         }
+        filters.push(desc);
+    }
+    if !filters.is_empty()
+    {
+        spans.push(sep());
+        spans.push(Span::raw(format!("filters: {}", filters.join("; "))));
     }
 
-	inputThreadHandle.join().unwrap();
-    Ok(())
+    if let Some(error) = &app.error
+    {
+        spans.push(sep());
+        spans.push(Span::styled(format!("{} (Esc)", error), Style::default().fg(app.theme.negative).add_modifier(Modifier::BOLD)));
+    }
+    spans.push(sep());
+    spans.push(Span::raw("? help"));
+    Paragraph::new(Spans::from(spans))
 }
 
+/*
+ * render_help
+ * centered overlay listing the keys that work in the current tab and panel
+ */
+fn render_help<B: Backend>(f: &mut Frame<B>, app: &types::App) {
+    let lines: Vec<Spans> = keys::active(app)
+        .map(|b| Spans::from(vec![
+            Span::styled(format!("{:>16}  ", b.key), app.theme.header().add_modifier(Modifier::BOLD)),
+            Span::raw(b.action),
+        ]))
+        .collect();
+
+    let size = f.size();
+    let width = 72.min(size.width);
+    let height = (lines.len() as u16 + 2).min(size.height);
+    let area = Rect::new((size.width - width) / 2, (size.height - height) / 2, width, height);
+    let help = Paragraph::new(lines)
+        .block(Block::default()
+            .title(Span::styled("Keys", app.theme.title()))
+            .borders(Borders::ALL)
+            .border_style(app.theme.border(true)));
+    f.render_widget(Clear, area);
+    f.render_widget(help, area);
+}
 
 /*
- * build_leaderboard
- * create leaderboard widget via App.leaderboard_stats 
+ * render_tab
+ * the tab's lists on the left, details of the selection or of what was drilled into on the right
  */
-fn build_leaderboard<'a>(app:&'a mut types::App) -> Table<'a> {
-    let mut color: Color = Color::Red; 
-    if app.focused == 4
+fn render_tab<B: Backend>(f: &mut Frame<B>, app: &mut types::App, area: Rect, compact: bool) {
+    // compact: the focused list, or the details once something was opened
+    let chunks = if !compact
+    {
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(45), Constraint::Percentage(55)].as_ref())
+            .split(area)
+    }
+    else if app.history.current.is_some()
     {
-        color = Color::White;
+        vec![Rect::default(), area]
+    }
+    else
+    {
+        vec![area, Rect::default()]
+    };
+
+    let panels = if compact && !app.tab.panels().is_empty() { vec![app.focused] } else { app.tab.panels().to_vec() };
+    // nothing is drawn into the empty area of a hidden list
+    if app.tab == types::Tab::MyTeam
+    {
+        // registrations and open votes below the results when logged in
+        let areas = if app.user.is_some() { split(chunks[0], Direction::Vertical, &[3, 1, 1]) } else { vec![chunks[0]] };
+        let mut state = app.my_team.state.clone();
+        f.render_stateful_widget(build_my_team(app), areas[0], &mut state);
+        app.my_team.state = state;
+        if let [_, registrations, votes] = areas[..]
+        {
+            let account = app.account.as_ref();
+            f.render_widget(build_account_events(app, "Registrations", account.map(|a| &a.registrations)), registrations);
+            f.render_widget(build_account_events(app, "Pending Votes", account.map(|a| &a.votes)), votes);
+        }
+    }
+    else
+    {
+        // the running list is short next to the others
+        let sizes: Vec<u16> = panels.iter().map(|p| if *p == Panel::Running { 1 } else { 2 }).collect();
+        for (panel, panel_area) in panels.into_iter().zip(split(chunks[0], Direction::Vertical, &sizes))
+        {
+            render_panel(f, app, panel, panel_area);
+        }
+    }
+    if chunks[1].area() == 0
+    {
+        return;
     }
-    let selected_style = Style::default().add_modifier(Modifier::REVERSED);
-    let normal_style = Style::default().fg(Color::Red);
 
-    // create surrounding block
     let block = Block::default()
-        .title(Span::styled(
-            "Leaderboard",
-            Style::default()
-                .fg(Color::Red)
-                .add_modifier(Modifier::BOLD),
-        ))
+        .title(Span::styled("Details", app.theme.title()))
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(color))
-        .title_alignment(Alignment::Left);
-    
-    // set up headers
-    let header_cells = [" ", " ", " ", " "]
-        .iter()
-        .map(|h| Cell::from(*h).style(Style::default().fg(Color::White)));
-    let header = Row::new(header_cells)
-        .style(normal_style)
-        .height(0);
-
-    // create rows of leaderboard data
-    let rows = app.leaderboard_stats.items.iter().map(|(a,b,c,d)| {
-        let height = 1;
-        let mut cells = vec![];
-        cells.push( Cell::from( String::from(a.clone()) ) );
-        cells.push( Cell::from( String::from(b.clone()) ) );
-        cells.push( Cell::from( String::from(c.clone()) ) );
-        cells.push( Cell::from( String::from(d.clone()) ) );
-        Row::new(cells).height(height as u16)
-    });
-
-    // render data into table
-    let t = Table::new(rows)
-        .header(header)
+        .border_style(app.theme.border(false));
+    let text = match app.history.current {
+        Some(types::Detail::Event(id)) => match app.events.get(&id) {
+            Some(event) => event_detail(app, event),
+            None if app.failing.contains("events") => vec![Spans::from("Could not load the event, see the status bar")],
+            None => vec![Spans::from("Loading event ...")],
+        },
+        Some(types::Detail::Team(id)) => match app.teams.get(&id) {
+            Some(team) => team_detail(team),
+            None if app.failing.contains("teams") => vec![Spans::from("Could not load the team, see the status bar")],
+            None => vec![Spans::from("Loading team ...")],
+        },
+        None => preview(app),
+    };
+    let paragraph = Paragraph::new(text)
         .block(block)
-        .highlight_style(selected_style)
-        .highlight_symbol(">> ")
-        .widths(&[
-            Constraint::Percentage(15),
-            Constraint::Percentage(40),
-            Constraint::Percentage(10),
-            Constraint::Percentage(35),
-        ]);
-    return t;
+        .wrap(Wrap { trim: false });
+    f.render_widget(paragraph, chunks[1]);
 }
 
-fn build_current_events<'a>(app :&'a mut types::App, width: usize) -> List<'a> {
-    let mut color: Color = Color::Red; 
-    if app.focused == 1
+fn build_my_team(app: &types::App) -> List<'_> {
+    let title = match app.config.team.id.and_then(|id| app.teams.get(&id)) {
+        Some(team) => format!("My Team: {}", team.name),
+        None => String::from("My Team"),
+    };
+    let block = Block::default()
+        .title(Span::styled(title, app.theme.title()))
+        .borders(Borders::ALL)
+        .border_style(app.theme.border(true));
+    if app.config.team.id.is_none()
     {
-        color = Color::White;
+        return List::new(vec![ListItem::new("Set [team] id in the config to follow your team")]).block(block);
     }
+    let items: Vec<ListItem> = app.my_team.items.iter()
+        .map(|r| ListItem::new(format!("{} #{:<4} {:>7.2}  {}", r.year, r.place, r.rating_points, r.event)))
+        .collect();
+    List::new(items)
+        .block(block)
+        .highlight_style(app.theme.highlight())
+        .highlight_symbol(">> ")
+}
 
+// events of the logged in member's account, None while they load
+fn build_account_events<'a>(app: &'a types::App, title: &'a str, events: Option<&'a Vec<types::AccountEvent>>) -> List<'a> {
     let block = Block::default()
+        .title(Span::styled(title, app.theme.title()))
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(color))
-        .title(Span::styled(
-            "Now Running",
-            Style::default()
-                .fg(Color::Red)
-                .add_modifier(Modifier::BOLD),
-        ))
-        .title_alignment(Alignment::Right);
-    // Iterate through all elements in the `items` app and append some debug text to it.
-    let items: Vec<ListItem> = app
-        .curr_events
-        .items
-        .iter()
-        .map(|name| {
-            ListItem::new(vec![
-                Spans::from("-".repeat(width)),
-                Spans::from(vec![Span::from(Span::styled(name, Style::default().add_modifier(Modifier::BOLD)))]),
-            ])
-        })
-        .collect();
+        .border_style(app.theme.border(false));
+    let items: Vec<ListItem> = match events {
+        None if app.failing.contains("account") => vec![ListItem::new("Not available, see the status bar")],
+        None => vec![ListItem::new("Loading ...")],
+        Some(events) if events.is_empty() => vec![ListItem::new("None")],
+        Some(events) => events.iter()
+            .map(|e| ListItem::new(format!("{}  {}", app.clock.maybe(e.start), e.title)))
+            .collect(),
+    };
+    List::new(items).block(block)
+}
 
-    // Create a List from all list items and highlight the currently selected one
-    let items = List::new(items)
-        .block(block)
-        .highlight_style(
-            Style::default()
-                .bg(Color::LightGreen)
-                .add_modifier(Modifier::BOLD),
-        )
-        .highlight_symbol(">> ");
-    return items;
+// "Label: value" line of the detail view
+fn field<'a>(label: &str, value: String) -> Spans<'a> {
+    Spans::from(vec![
+        Span::styled(format!("{}: ", label), Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(value),
+    ])
+}
+
+fn date_field<'a>(clock: &dates::Clock, label: &str, date: Option<chrono::DateTime<chrono::Utc>>) -> Spans<'a> {
+    field(label, clock.maybe(date))
+}
+
+// how long an event runs, when both ends are known
+fn duration_field<'a>(start: Option<chrono::DateTime<chrono::Utc>>, finish: Option<chrono::DateTime<chrono::Utc>>) -> Spans<'a> {
+    let duration = match (start, finish) {
+        (Some(start), Some(finish)) => format_duration(finish - start),
+        _ => String::from("-"),
+    };
+    field("Duration", duration)
 }
 
-fn build_past_events<'a>(app :&'a mut types::App, width: usize) -> List<'a> {
-    let mut color: Color = Color::Red; 
-    if app.focused == 2
+fn event_detail(app: &types::App, event: &types::EventDetail) -> Vec<Spans<'static>> {
+    let clock = &app.clock;
+    let mut lines = vec![
+        Spans::from(Span::styled(event.title.clone(), Style::default().add_modifier(Modifier::BOLD))),
+        Spans::from(""),
+        date_field(clock, "Start", event.start),
+        date_field(clock, "Finish", event.finish),
+        duration_field(event.start, event.finish),
+        field("Format", event.format.clone()),
+        field("Weight", format!("{:.2}", event.weight)),
+        field("Location", if event.onsite { event.location.clone() } else { String::from("Online") }),
+        field("Participants", event.participants.to_string()),
+        field("Organizers", event.organizers.join(", ")),
+        field("Url", event.url.clone()),
+        field("Ctftime", format!("https://ctftime.org/event/{}", event.id)),
+        Spans::from(""),
+    ];
+    if event.start.is_some_and(|start| start > chrono::Utc::now())
     {
-        color = Color::White;
+        lines.extend(what_if(app, event));
     }
-    let block = Block::default()
-        .borders(Borders::ALL)
-        .border_style(Style::default().fg(color))
-        .title(Span::styled(
-            "Past Events",
-            Style::default()
-                .fg(Color::Red)
-                .add_modifier(Modifier::BOLD),
-        ))
-        .title_alignment(Alignment::Right);
-    // Iterate through all elements in the `items` app and append some debug text to it.
-    let items: Vec<ListItem> = app
-        .past_events_list
-        .items
-        .iter()
-        .map(|(name, data)| {
-            ListItem::new(vec![
-                Spans::from("-".repeat(width)),
-                Spans::from(vec![Span::from(Span::styled(name, Style::default().add_modifier(Modifier::BOLD)))]),
-                Spans::from(vec![Span::raw(data)]),
-            ])
-        })
-        .collect();
+    else if let Some(rows) = app.scoreboards.get(&event.id).filter(|rows| !rows.is_empty())
+    {
+        lines.extend(results(app, event, rows));
+    }
+    lines.extend(event.description.lines().map(|l| Spans::from(l.to_string())));
+    lines
+}
 
-    // Create a List from all list items and highlight the currently selected one
-    let items = List::new(items)
-        .block(block)
-        .highlight_style(
-            Style::default()
-                .bg(Color::LightGreen)
-                .add_modifier(Modifier::BOLD),
-        )
-        .highlight_symbol(">> ");
-    return items;
+/*
+ * results
+ * the scoreboard with the rating points each place was worth,
+ * computed from the event weight, our team highlighted
+ */
+fn results(app: &types::App, event: &types::EventDetail, rows: &[types::ScoreRow]) -> Vec<Spans<'static>> {
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let mut lines = vec![
+        Spans::from(Span::styled(format!("Results ({} teams, weight {:.2})", rows.len(), event.weight), bold)),
+        Spans::from(Span::styled(format!("{:>5}  {:>10}  {:>8}  Team", "Place", "Points", "Rating"), app.theme.header())),
+    ];
+    for (row, rating) in rows.iter().zip(rating::scoreboard(rows, event.weight))
+    {
+        let line = format!("{:>5}  {:>10.3}  {:>8.3}  {}", row.place, row.points, rating, row.team);
+        let ours = row.team_id.is_some() && row.team_id == app.config.team.id;
+        lines.push(if ours { Spans::from(Span::styled(line, app.theme.highlight())) } else { Spans::from(line) });
+    }
+    lines.push(Spans::from(""));
+    lines
 }
 
-fn build_writeups<'a>(app :&'a mut types::App, width: usize) -> List<'a> {
-    // NOTE: each entry has a height 6.5
-    let mut color: Color = Color::Red; 
-    if app.focused == 3
+/*
+ * what_if
+ * the rating points our hypothetical placement would be worth,
+ * and a few other places with the same share of the winner's points for scale
+ */
+fn what_if(app: &types::App, event: &types::EventDetail) -> Vec<Spans<'static>> {
+    let w = app.what_if;
+    // registered teams, the field is usually larger than that once the event runs
+    let teams = if event.participants > 0 { event.participants } else { 100 }.max(w.place);
+    let mut lines = vec![
+        Spans::from(Span::styled(format!("What if (weight {:.2}, {} teams)", event.weight, teams), Style::default().add_modifier(Modifier::BOLD))),
+        Spans::from(Span::styled(
+            format!("place {}, {}% of the winner's points: {:.3} rating points", w.place, if w.place == 1 { 100 } else { w.share }, w.points(event.weight, teams)),
+            app.theme.highlight(),
+        )),
+    ];
+    for place in [1, 3, 5, 10, 25, 50].into_iter().filter(|p| *p != w.place && *p <= teams)
     {
-        color = Color::White;
+        let other = rating::WhatIf { place, share: w.share };
+        lines.push(Spans::from(format!("place {:<4} {:>8.3}", place, other.points(event.weight, teams))));
     }
-    let block = Block::default()
-        .borders(Borders::ALL)
-        .border_style(Style::default().fg(color))
-        .title(Span::styled(
-            "Write Ups",
-            Style::default()
-                .fg(Color::Red)
-                .add_modifier(Modifier::BOLD),
-        ))
-        .title_alignment(Alignment::Right);
-    // Iterate through all elements in the `items` app and append some debug text to it.
-    let items: Vec<ListItem> = app
-        .writeups
-        .items
-        .iter()
-        .map(|(w1, w2, w3, w4, w5)| {
-            ListItem::new(vec![
-                Spans::from(vec![
-                            Span::raw("["), Span::raw(w3), Span::raw("]"),
-                            /*Span::raw(w1), Span::raw(" "), */Span::raw(w2),]),
-            ])
-        })
-        .collect();
+    lines.push(Spans::from(Span::styled(
+        "'+' / '-' change the place, '<' / '>' the share of the winner's points",
+        Style::default().add_modifier(Modifier::ITALIC),
+    )));
+    lines.push(Spans::from(""));
+    lines
+}
 
-    // Create a List from all list items and highlight the currently selected one
-    let items = List::new(items)
-        .block(block)
-        .highlight_style(
-            Style::default()
-                .bg(Color::LightGreen)
-                .add_modifier(Modifier::BOLD),
-        )
-        .highlight_symbol(">> ");
-    return items;
+fn team_detail(team: &types::TeamHistory) -> Vec<Spans<'static>> {
+    let mut lines = vec![
+        Spans::from(Span::styled(team_summary(team), Style::default().add_modifier(Modifier::BOLD))),
+        field("Ctftime", format!("https://ctftime.org/team/{}", team.id)),
+        Spans::from(""),
+    ];
+    for (year, points, place, country_place) in team.years.iter().rev()
+    {
+        let rank = |r: &Option<u32>| r.map(|r| format!("#{}", r)).unwrap_or_else(|| String::from("-"));
+        lines.push(Spans::from(format!(
+            "{}  {:>8.2} points  {:>6} global  {:>6} country",
+            year, points, rank(place), rank(country_place)
+        )));
+    }
+    lines.push(Spans::from(""));
+    for r in &team.results
+    {
+        lines.push(Spans::from(format!("{} #{:<4} {:>7.2}  {}", r.year, r.place, r.rating_points, r.event)));
+    }
+    lines
+}
+
+/*
+ * preview
+ * detail lines for the row selected in the focused list, before drilling in
+ */
+fn preview(app: &types::App) -> Vec<Spans<'static>> {
+    let hint = |s: &str| Spans::from(Span::styled(s.to_string(), Style::default().add_modifier(Modifier::ITALIC)));
+    if app.tab == types::Tab::MyTeam
+    {
+        return match app.my_team.state.selected().and_then(|i| app.my_team.items.get(i)) {
+            Some(r) => vec![
+                Spans::from(Span::styled(r.event.clone(), Style::default().add_modifier(Modifier::BOLD))),
+                field("Year", r.year.to_string()),
+                field("Place", r.place.to_string()),
+                field("Rating points", format!("{:.2}", r.rating_points)),
+                Spans::from(""),
+                hint("Enter opens the event"),
+            ],
+            None => vec![],
+        };
+    }
+
+    match app.focused {
+        Panel::Running => match app.curr_events.state.selected().and_then(|i| app.curr_events.items.get(i)) {
+            Some(e) => vec![
+                Spans::from(Span::styled(e.title.clone(), Style::default().add_modifier(Modifier::BOLD))),
+                date_field(&app.clock, "Start", e.start),
+                date_field(&app.clock, "Finish", e.finish),
+                field("Left", e.remaining(chrono::Utc::now()).map(format_duration).unwrap_or_default()),
+                field("Format", e.format.clone()),
+                field("Weight", format!("{:.2}", e.weight)),
+                field("Organizers", e.organizers.join(", ")),
+                Spans::from(""),
+                hint("Enter opens the event"),
+            ],
+            None => vec![],
+        },
+        Panel::Main if app.main_view == types::MainView::Upcoming => {
+            match app.upcoming.state.selected().and_then(|i| app.upcoming.items.get(i)) {
+                Some(e) => vec![
+                    Spans::from(Span::styled(e.title.clone(), Style::default().add_modifier(Modifier::BOLD))),
+                    date_field(&app.clock, "Start", Some(e.start)),
+                    date_field(&app.clock, "Finish", Some(e.finish)),
+                    duration_field(Some(e.start), Some(e.finish)),
+                    field("Format", e.format.clone()),
+                    field("Weight", format!("{:.2}", e.weight)),
+                    field("Location", String::from(if e.onsite { "On-site" } else { "Online" })),
+                    Spans::from(""),
+                    hint("Enter opens the event"),
+                ],
+                None => vec![],
+            }
+        }
+        Panel::Main => match app.past_events_list.state.selected().and_then(|i| app.past_events_list.items.get(i)) {
+            Some(e) => {
+                let mut lines = vec![Spans::from(Span::styled(e.title.clone(), Style::default().add_modifier(Modifier::BOLD)))];
+                if e.start.is_some()
+                {
+                    lines.push(date_field(&app.clock, "Start", e.start));
+                    lines.push(date_field(&app.clock, "Finish", e.finish));
+                    lines.push(duration_field(e.start, e.finish));
+                }
+                else
+                {
+                    lines.push(field("Date", e.date.clone()));
+                }
+                lines.push(field("Followed", String::from(if app.is_watched(&e.title) { "yes" } else { "no" })));
+                if e.id.is_some()
+                {
+                    lines.push(Spans::from(""));
+                    lines.push(hint("Enter opens the event"));
+                }
+                lines
+            }
+            None => vec![],
+        },
+        Panel::Leaderboard => match app.leaderboard_stats.state.selected().and_then(|i| app.leaderboard_stats.items.get(i)) {
+            Some(t) => {
+                // the full history once it was loaded
+                if let Some(team) = t.team_id.and_then(|id| app.teams.get(&id))
+                {
+                    return team_detail(team);
+                }
+                vec![
+                    Spans::from(Span::styled(t.name.clone(), Style::default().add_modifier(Modifier::BOLD))),
+                    field("Rank", t.rank.clone()),
+                    field("Points", t.points.clone()),
+                    field("Country", t.country.clone()),
+                    Spans::from(""),
+                    hint("Enter loads the team's history"),
+                ]
+            }
+            None => vec![],
+        },
+        Panel::Writeups => match app.writeups.state.selected().and_then(|i| app.writeups.items.get(i)) {
+            Some(w) => vec![
+                Spans::from(Span::styled(w.1.trim().to_string(), Style::default().add_modifier(Modifier::BOLD))),
+                field("Event", w.0.trim().to_string()),
+                field("Tags", w.2.trim().to_string()),
+                field("Author", w.3.trim().to_string()),
+                field("Action", w.4.trim().to_string()),
+            ],
+            None => vec![],
+        },
+        Panel::Watchlist => match app.watchlist.state.selected().and_then(|i| app.watchlist.items.get(i)) {
+            Some(title) => {
+                let status = if app.curr_events.items.iter().any(|e| e.title.trim().eq_ignore_ascii_case(title.trim()))
+                {
+                    "running now"
+                }
+                else if app.upcoming.items.iter().any(|e| e.title.trim().eq_ignore_ascii_case(title.trim()))
+                {
+                    "upcoming"
+                }
+                else
+                {
+                    "not listed"
+                };
+                vec![
+                    Spans::from(Span::styled(title.clone(), Style::default().add_modifier(Modifier::BOLD))),
+                    field("Status", String::from(status)),
+                    Spans::from(""),
+                    hint("Enter opens the event when it is running or upcoming"),
+                ]
+            }
+            None => vec![],
+        },
+    }
 }
 
 fn ui<B: Backend>(f: &mut Frame<B>, app: &mut types::App) {
@@ -451,47 +2127,41 @@ fn ui<B: Backend>(f: &mut Frame<B>, app: &mut types::App) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
-        .constraints([Constraint::Percentage(75), Constraint::Percentage(25)].as_ref())
-        .split(f.size());
-
-    // Top two inner blocks
-    let top_chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
-        .split(chunks[0]);
-
-    let top_left_chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Percentage(20), Constraint::Percentage(80)].as_ref())
-        .split(top_chunks[0]);
-
-    // build widgets
-	f.render_widget(build_leaderboard(app), top_left_chunks[1]);
-    f.render_widget(build_current_events(app, top_left_chunks[0].width as usize), top_left_chunks[0]);
+        .constraints([Constraint::Length(1), Constraint::Min(0), Constraint::Length(1)].as_ref())
+        .split(size);
 
-    // build past events widget
-    f.render_widget(build_past_events(app, top_chunks[1].width as usize), top_chunks[1]);
-
-    // Bottom two inner blocks
-    let bottom_chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
-        .split(chunks[1]);
+    let titles: Vec<Spans> = types::Tab::ALL.iter()
+        .enumerate()
+        .map(|(i, tab)| Spans::from(format!("{} {}", i + 1, tab.title())))
+        .collect();
+    let selected = types::Tab::ALL.iter().position(|t| *t == app.tab).unwrap_or(0);
+    let tabs = Tabs::new(titles)
+        .select(selected)
+        .highlight_style(app.theme.title().add_modifier(Modifier::UNDERLINED));
+    f.render_widget(tabs, chunks[0]);
 
-    // Bottom left block with all default borders
-    f.render_widget(build_writeups(app, bottom_chunks[0].width as usize), bottom_chunks[0]);
+    // small terminals get one panel at a time, switched with 'W' / 'S' and the tabs
+    let compact = app.config.layout.compact(size.width, size.height);
+    if app.tab != types::Tab::Dashboard
+    {
+        render_tab(f, app, chunks[1], compact);
+    }
+    else if app.maximized || compact
+    {
+        render_panel(f, app, app.focused, chunks[1]);
+    }
+    else
+    {
+        for (panel, area) in layout_areas(&app.config.layout, chunks[1])
+        {
+            render_panel(f, app, panel, area);
+        }
+    }
 
-    // Bottom right block with styled left and right border
-    // build calender
-    let block = Block::default()
-        .title(Span::styled(
-            "Write Up Watchlist",
-            Style::default()
-                .fg(Color::Red)
-                .add_modifier(Modifier::BOLD),
-        ))
-        .border_style(Style::default().fg(Color::Red))
-        .borders(Borders::ALL);
-    f.render_widget(block, bottom_chunks[1]);
+    f.render_widget(build_status(app), chunks[2]);
+    render_toasts(f, app);
+    if app.show_help
+    {
+        render_help(f, app);
+    }
 }
-
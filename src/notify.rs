@@ -0,0 +1,152 @@
+use crate::config::{EventFilter, NotificationConfig};
+use crate::types::{RunningEvent, UpcomingEvent, Writeup};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    io::{self, Write},
+    process::{Command, Stdio},
+    time::Instant,
+};
+
+// a message shown in the corner of the tui until `until`
+pub struct Toast {
+    pub message: String,
+    pub until: Instant,
+}
+
+/*
+ * Watcher
+ * remembers what the feeds looked like on the previous refresh
+ * so only new running events, writeups and start reminders are announced
+ */
+// writeups remembered, a few pages' worth so one dropping off the first page is not announced again
+const MAX_WRITEUP_KEYS: usize = 500;
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Watcher {
+    // None until the first refresh, which only records a baseline
+    running: Option<HashSet<String>>,
+    // oldest first, at most MAX_WRITEUP_KEYS
+    writeups: Option<Vec<String>>,
+    // UpcomingEvent::key of the events seen and reminded of
+    upcoming: Option<HashSet<String>>,
+    reminded: HashSet<String>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, evt: &UpcomingEvent) -> bool {
+        self.enabled
+            && evt.weight >= self.min_weight
+            && (self.formats.is_empty() || self.formats.iter().any(|f| f.eq_ignore_ascii_case(&evt.format)))
+            && !(self.online_only && evt.onsite)
+    }
+}
+
+impl Watcher {
+    // titles in the running feed that were not there last time
+    pub fn running(&mut self, events: &[RunningEvent]) -> Vec<String> {
+        let current: HashSet<String> = events.iter().map(|e| e.title.clone()).collect();
+        let msgs = match &self.running {
+            Some(prev) => events.iter()
+                .filter(|e| !prev.contains(&e.title))
+                .map(|e| format!("Now running: {}", e.title))
+                .collect(),
+            None => vec![],
+        };
+        self.running = Some(current);
+        msgs
+    }
+
+    // writeups for watched events that were not listed last time
+    pub fn writeups(&mut self, rows: &[Writeup], watched: impl Fn(&str) -> bool) -> Vec<String> {
+        let key = |w: &Writeup| format!("{}\u{1f}{}\u{1f}{}", w.0, w.1, w.3);
+        let msgs = match &self.writeups {
+            Some(prev) => rows.iter()
+                .filter(|w| !prev.contains(&key(w)) && watched(&w.0))
+                .map(|w| format!("New writeup for {}: {} by {}", w.0.trim(), w.1.trim(), w.3.trim()))
+                .collect(),
+            None => vec![],
+        };
+        // keep earlier keys so a writeup dropping off the first page is not announced again,
+        // the ones seen now move to the end and the oldest are forgotten
+        let mut seen = self.writeups.take().unwrap_or_default();
+        for k in rows.iter().map(key)
+        {
+            seen.retain(|s| *s != k);
+            seen.push(k);
+        }
+        seen.drain(..seen.len().saturating_sub(MAX_WRITEUP_KEYS));
+        self.writeups = Some(seen);
+        msgs
+    }
+
+    // newly listed upcoming events that match the filter
+    pub fn new_upcoming(&mut self, events: &[UpcomingEvent], filter: &EventFilter) -> Vec<String> {
+        let msgs = match &self.upcoming {
+            Some(prev) => events.iter()
+                .filter(|e| !prev.contains(&e.key()) && filter.matches(e))
+                .map(|e| format!("New upcoming event: {} ({}, weight {:.2}) starts {}", e.title, e.format, e.weight, e.start.format("%Y-%m-%d %H:%M UTC")))
+                .collect(),
+            None => vec![],
+        };
+        let mut merged = self.upcoming.take().unwrap_or_default();
+        merged.extend(events.iter().map(UpcomingEvent::key));
+        self.upcoming = Some(merged);
+        msgs
+    }
+
+    // watched or filtered events starting within the lead time, each announced once
+    pub fn upcoming(&mut self, events: &[UpcomingEvent], lead_minutes: i64, filter: &EventFilter, watched: impl Fn(&str) -> bool) -> Vec<String> {
+        let now = Utc::now();
+        let horizon = now + Duration::minutes(lead_minutes);
+        let mut msgs = vec![];
+        for evt in events {
+            if evt.start > now && evt.start <= horizon
+                && (watched(&evt.title) || filter.matches(evt))
+                && self.reminded.insert(evt.key())
+            {
+                let minutes = (evt.start - now).num_minutes();
+                msgs.push(format!("{} starts in {} min", evt.title, minutes));
+            }
+        }
+        msgs
+    }
+}
+
+/*
+ * deliver
+ * ring the bell, raise a desktop notification and queue a toast for a message
+ */
+pub fn deliver(config: &NotificationConfig, message: &str, toasts: &mut Vec<Toast>) {
+    if config.bell
+    {
+        let mut stdout = io::stdout();
+        let _ = stdout.write_all(b"\x07");
+        let _ = stdout.flush();
+    }
+    if config.desktop
+    {
+        // notify-send talks to the desktop's notification service over d-bus,
+        // silently skip it when it isn't installed
+        if let Ok(mut child) = Command::new("notify-send")
+            .arg("CTF>TERM")
+            .arg(message)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            std::thread::spawn(move || child.wait());
+        }
+    }
+    toast(config, message, toasts);
+}
+
+// shown in the corner of the tui for toast_secs
+fn toast(config: &NotificationConfig, message: &str, toasts: &mut Vec<Toast>) {
+    toasts.push(Toast {
+        message: message.to_string(),
+        until: Instant::now() + std::time::Duration::from_secs(config.toast_secs),
+    });
+}
@@ -0,0 +1,127 @@
+use crate::types::ScoreRow;
+
+/*
+ * points
+ * rating points of one placement by ctftime's published formula
+ * (https://ctftime.org/rating-formula):
+ *   points_coef = team_points / best_points
+ *   place_coef = 1 / team_place
+ *   rating = (points_coef + place_coef) * weight / (1 / (1 + team_place / total_teams))
+ * teams without points get nothing
+ */
+pub fn points(place: u32, team_points: f64, best_points: f64, weight: f64, total_teams: u32) -> f64 {
+    if place == 0 || best_points <= 0.0 || team_points <= 0.0
+    {
+        return 0.0;
+    }
+    let points_coef = team_points / best_points;
+    let place_coef = 1.0 / place as f64;
+    let total = total_teams.max(place) as f64;
+    (points_coef + place_coef) * weight / (1.0 / (1.0 + place as f64 / total))
+}
+
+// rating points of every row of a scoreboard, in its order
+pub fn scoreboard(rows: &[ScoreRow], weight: f64) -> Vec<f64> {
+    let best = rows.iter().map(|r| r.points).fold(0.0, f64::max);
+    let total = rows.len() as u32;
+    rows.iter().map(|r| points(r.place, r.points, best, weight, total)).collect()
+}
+
+/*
+ * WhatIf
+ * a hypothetical placement in an upcoming event: our place and our points
+ * as a share of the winner's, changed with '+' / '-' and '<' / '>'
+ */
+#[derive(Clone, Copy)]
+pub struct WhatIf {
+    pub place: u32,
+    // percent of the winner's points
+    pub share: u32,
+}
+
+impl Default for WhatIf {
+    fn default() -> Self {
+        WhatIf { place: 10, share: 50 }
+    }
+}
+
+impl WhatIf {
+    pub fn place_by(&mut self, delta: i64) {
+        self.place = (self.place as i64 + delta).max(1) as u32;
+    }
+
+    pub fn share_by(&mut self, delta: i64) {
+        self.share = (self.share as i64 + delta).clamp(1, 100) as u32;
+    }
+
+    // the winner has the best points by definition
+    pub fn points(&self, weight: f64, total_teams: u32) -> f64 {
+        let share = if self.place == 1 { 100 } else { self.share };
+        points(self.place, share as f64, 100.0, weight, total_teams)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // expected values are the published formula worked by hand,
+    // for a weight 25 event with 100 teams unless noted
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn first_place() {
+        // (1000/1000 + 1/1) * 25 * (1 + 1/100)
+        assert!(close(points(1, 1000.0, 1000.0, 25.0, 100), 50.5));
+    }
+
+    #[test]
+    fn middle_place() {
+        // (400/1000 + 1/2) * 25 * (1 + 2/100)
+        assert!(close(points(2, 400.0, 1000.0, 25.0, 100), 22.95));
+    }
+
+    #[test]
+    fn last_place() {
+        // (10/1000 + 1/100) * 25 * (1 + 100/100)
+        assert!(close(points(100, 10.0, 1000.0, 25.0, 100), 1.0));
+    }
+
+    #[test]
+    fn no_points_no_rating() {
+        assert_eq!(points(50, 0.0, 1000.0, 25.0, 100), 0.0);
+        assert_eq!(points(0, 10.0, 1000.0, 25.0, 100), 0.0);
+        assert_eq!(points(1, 10.0, 0.0, 25.0, 100), 0.0);
+        // a weight 0 event is not rated
+        assert_eq!(points(1, 1000.0, 1000.0, 0.0, 100), 0.0);
+    }
+
+    #[test]
+    fn scoreboard_uses_the_best_and_the_row_count() {
+        let row = |place, points| ScoreRow { place, team: String::new(), team_id: None, points };
+        let rows = [row(1, 300.0), row(2, 150.0), row(3, 0.0), row(4, 30.0)];
+        let rating = scoreboard(&rows, 10.0);
+        // (1 + 1) * 10 * (1 + 1/4), (0.5 + 0.5) * 10 * (1 + 2/4), nothing, (0.1 + 0.25) * 10 * 2
+        for (got, want) in rating.iter().zip([25.0, 15.0, 0.0, 7.0])
+        {
+            assert!(close(*got, want), "{} != {}", got, want);
+        }
+    }
+
+    #[test]
+    fn what_if() {
+        let mut w = WhatIf { place: 2, share: 40 };
+        assert!(close(w.points(25.0, 100), 22.95));
+        // the winner always has all of the best points
+        w.place_by(-5);
+        assert_eq!(w.place, 1);
+        assert!(close(w.points(25.0, 100), 50.5));
+        w.share_by(100);
+        assert_eq!(w.share, 100);
+        w.share_by(-200);
+        assert_eq!(w.share, 1);
+    }
+}
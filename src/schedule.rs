@@ -0,0 +1,254 @@
+use crate::config::Config;
+use crate::types::UpcomingEvent;
+use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, error::Error, fs, path::PathBuf};
+
+// what the team plans to do about an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Plan {
+    Playing,
+    Maybe,
+    Skip,
+}
+
+impl Plan {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Plan::Playing => "playing",
+            Plan::Maybe => "maybe",
+            Plan::Skip => "skip",
+        }
+    }
+}
+
+// a marked event, with its times so it stays on the schedule once it is no longer listed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Registration {
+    pub title: String,
+    pub plan: Plan,
+    pub start: DateTime<Utc>,
+    pub finish: DateTime<Utc>,
+}
+
+/*
+ * Registrations
+ * events marked playing / maybe / skip by UpcomingEvent::key,
+ * kept in $XDG_DATA_HOME/ctfterm/registrations.json
+ */
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Registrations {
+    pub events: BTreeMap<String, Registration>,
+}
+
+impl Registrations {
+    fn path() -> Option<PathBuf> {
+        Some(Config::data_dir()?.join("registrations.json"))
+    }
+
+    pub fn load() -> Registrations {
+        Registrations::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = Registrations::path().ok_or("no data directory")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn plan(&self, evt: &UpcomingEvent) -> Option<Plan> {
+        self.events.get(&evt.key()).map(|r| r.plan)
+    }
+
+    // unmarked -> playing -> maybe -> skip -> unmarked
+    pub fn cycle(&mut self, evt: &UpcomingEvent) {
+        let next = match self.plan(evt) {
+            None => Some(Plan::Playing),
+            Some(Plan::Playing) => Some(Plan::Maybe),
+            Some(Plan::Maybe) => Some(Plan::Skip),
+            Some(Plan::Skip) => None,
+        };
+        match next {
+            Some(plan) => {
+                self.events.insert(evt.key(), Registration {
+                    title: evt.title.clone(),
+                    plan,
+                    start: evt.start,
+                    finish: evt.finish,
+                });
+            }
+            None => {
+                self.events.remove(&evt.key());
+            }
+        }
+    }
+}
+
+// one event on the weekly schedule
+pub struct Slot {
+    pub title: String,
+    pub plan: Option<Plan>,
+    pub start: DateTime<Utc>,
+    pub finish: DateTime<Utc>,
+    // titles of the other slots running at the same time
+    pub overlaps: Vec<String>,
+    // overlaps with another event we are playing
+    pub conflict: bool,
+}
+
+impl Slot {
+    fn overlaps(&self, other: &Slot) -> bool {
+        self.start < other.finish && other.start < self.finish
+    }
+
+    // hours of the event that fall into [from, to)
+    pub fn hours_in(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> f64 {
+        let start = self.start.max(from);
+        let finish = self.finish.min(to);
+        if finish <= start { 0.0 } else { (finish - start).num_minutes() as f64 / 60.0 }
+    }
+}
+
+// monday 00:00 UTC of the week `offset` weeks from now
+pub fn week_start(offset: i64) -> DateTime<Utc> {
+    let today = Utc::now().naive_utc().date();
+    let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    Utc.from_utc_datetime(&monday.and_hms_opt(0, 0, 0).unwrap_or_default()) + Duration::weeks(offset)
+}
+
+/*
+ * week
+ * marked and still unmarked upcoming events touching the week from `from`,
+ * skipped ones left out, with their overlaps and playing conflicts
+ */
+pub fn week(regs: &Registrations, upcoming: &[UpcomingEvent], from: DateTime<Utc>) -> Vec<Slot> {
+    let to = from + Duration::weeks(1);
+    let mut slots: Vec<Slot> = regs.events.values()
+        .filter(|r| r.plan != Plan::Skip)
+        .map(|r| Slot { title: r.title.clone(), plan: Some(r.plan), start: r.start, finish: r.finish, overlaps: vec![], conflict: false })
+        .collect();
+    slots.extend(upcoming.iter()
+        .filter(|e| !regs.events.contains_key(&e.key()))
+        .map(|e| Slot { title: e.title.clone(), plan: None, start: e.start, finish: e.finish, overlaps: vec![], conflict: false }));
+    slots.retain(|s| s.start < to && s.finish > from);
+    slots.sort_by_key(|s| s.start);
+
+    for i in 0..slots.len()
+    {
+        let mut overlaps = vec![];
+        let mut conflict = false;
+        for j in 0..slots.len()
+        {
+            if i != j && slots[i].overlaps(&slots[j])
+            {
+                overlaps.push(slots[j].title.clone());
+                conflict |= slots[i].plan == Some(Plan::Playing) && slots[j].plan == Some(Plan::Playing);
+            }
+        }
+        slots[i].overlaps = overlaps;
+        slots[i].conflict = conflict;
+    }
+    slots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // monday 2026-03-02 00:00 UTC plus some hours
+    fn at(hours: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 2, 0, 0, 0).unwrap() + Duration::hours(hours)
+    }
+
+    fn event(id: u32, title: &str, start: i64, finish: i64) -> UpcomingEvent {
+        UpcomingEvent {
+            id: Some(id),
+            title: title.to_string(),
+            start: at(start),
+            finish: at(finish),
+            format: String::new(),
+            weight: 0.0,
+            onsite: false,
+        }
+    }
+
+    fn marked(events: &[(&UpcomingEvent, Plan)]) -> Registrations {
+        let mut regs = Registrations::default();
+        for (evt, plan) in events
+        {
+            regs.events.insert(evt.key(), Registration { title: evt.title.clone(), plan: *plan, start: evt.start, finish: evt.finish });
+        }
+        regs
+    }
+
+    fn slot<'a>(slots: &'a [Slot], title: &str) -> &'a Slot {
+        slots.iter().find(|s| s.title == title).unwrap()
+    }
+
+    #[test]
+    fn touching_events_do_not_overlap() {
+        let a = event(1, "A", 10, 20);
+        let b = event(2, "B", 20, 30);
+        let slots = week(&marked(&[(&a, Plan::Playing), (&b, Plan::Playing)]), &[a, b], at(0));
+        assert_eq!(slots.len(), 2);
+        assert!(slots.iter().all(|s| s.overlaps.is_empty() && !s.conflict));
+    }
+
+    #[test]
+    fn overlaps_and_conflicts() {
+        let a = event(1, "A", 10, 20);
+        let b = event(2, "B", 19, 30);
+        let c = event(3, "C", 25, 40);
+        let regs = marked(&[(&a, Plan::Playing), (&b, Plan::Playing), (&c, Plan::Maybe)]);
+        let slots = week(&regs, &[a, b, c], at(0));
+        assert_eq!(slot(&slots, "A").overlaps, vec!["B"]);
+        assert_eq!(slot(&slots, "B").overlaps, vec!["A", "C"]);
+        assert_eq!(slot(&slots, "C").overlaps, vec!["B"]);
+        // only two events we play at the same time conflict
+        assert!(slot(&slots, "A").conflict && slot(&slots, "B").conflict);
+        assert!(!slot(&slots, "C").conflict);
+    }
+
+    #[test]
+    fn unmarked_and_skipped() {
+        let a = event(1, "A", 10, 20);
+        let b = event(2, "B", 15, 25);
+        let c = event(3, "C", 15, 25);
+        let regs = marked(&[(&a, Plan::Playing), (&c, Plan::Skip)]);
+        let slots = week(&regs, &[a, b, c], at(0));
+        // a skipped event is left out, an unmarked one overlaps without a conflict
+        assert_eq!(slots.iter().map(|s| s.title.as_str()).collect::<Vec<_>>(), vec!["A", "B"]);
+        assert_eq!(slot(&slots, "B").plan, None);
+        assert!(!slot(&slots, "A").conflict && !slot(&slots, "B").conflict);
+        assert_eq!(slot(&slots, "A").overlaps, vec!["B"]);
+    }
+
+    #[test]
+    fn events_spanning_the_week_boundary() {
+        // sunday 18:00 to tuesday 06:00 of the next week, and one entirely next week
+        let across = event(1, "Across", 6 * 24 + 18, 8 * 24 + 6);
+        let later = event(2, "Later", 8 * 24, 9 * 24);
+        let regs = marked(&[(&across, Plan::Playing), (&later, Plan::Playing)]);
+        let upcoming = [across, later];
+
+        let this_week = week(&regs, &upcoming, at(0));
+        assert_eq!(this_week.len(), 1);
+        // the clash with Later happens next week and shows there
+        assert!(this_week[0].overlaps.is_empty() && !this_week[0].conflict);
+        let next_week = week(&regs, &upcoming, at(7 * 24));
+        assert_eq!(next_week.len(), 2);
+        assert!(next_week.iter().all(|s| s.conflict));
+
+        // only the hours inside each week count towards it
+        assert_eq!(slot(&this_week, "Across").hours_in(at(0), at(7 * 24)), 6.0);
+        assert_eq!(slot(&next_week, "Across").hours_in(at(7 * 24), at(14 * 24)), 30.0);
+    }
+}
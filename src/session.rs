@@ -0,0 +1,155 @@
+use crate::config::{AccountConfig, Config};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyModifiers},
+    terminal::{disable_raw_mode, enable_raw_mode},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+const SERVICE: &str = "ctfterm";
+const USER: &str = "ctftime";
+
+// what we log in to ctftime with
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Credentials {
+    Password { username: String, password: String },
+    // the sessionid cookie of a browser that is logged in
+    Cookie { sessionid: String },
+}
+
+fn path() -> Option<PathBuf> {
+    Some(Config::data_dir()?.join("credentials.json"))
+}
+
+fn keyring() -> Option<keyring::Entry> {
+    keyring::Entry::new(SERVICE, USER).ok()
+}
+
+/*
+ * load
+ * saved credentials from the OS keyring, or from
+ * $XDG_DATA_HOME/ctfterm/credentials.json when there is no keyring
+ */
+pub fn load(config: &AccountConfig) -> Option<Credentials> {
+    let from_keyring = || keyring()?.get_password().ok();
+    let from_file = || fs::read_to_string(path()?).ok();
+    let json = if config.keyring { from_keyring().or_else(from_file) } else { from_file() };
+    serde_json::from_str(&json?).ok()
+}
+
+// into the keyring when there is one, otherwise into a file only we can read
+pub fn save(config: &AccountConfig, credentials: &Credentials) -> Result<&'static str, Box<dyn Error>> {
+    let json = serde_json::to_string(credentials)?;
+    if config.keyring
+    {
+        if let Some(Ok(())) = keyring().map(|entry| entry.set_password(&json))
+        {
+            return Ok("the OS keyring");
+        }
+    }
+    let path = path().ok_or("no data directory")?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // only readable by us before anything is written, also when the file was already there
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(json.as_bytes())?;
+    Ok("credentials.json")
+}
+
+pub fn clear() {
+    if let Some(entry) = keyring()
+    {
+        let _ = entry.delete_password();
+    }
+    if let Some(path) = path()
+    {
+        let _ = fs::remove_file(path);
+    }
+}
+
+// read a line from the terminal, without echo for passwords
+fn prompt(label: &str, hidden: bool) -> io::Result<String> {
+    print!("{}", label);
+    io::stdout().flush()?;
+    if !hidden
+    {
+        let mut line = String::new();
+        io::stdin().read_line(&mut line)?;
+        return Ok(line.trim().to_string());
+    }
+    enable_raw_mode()?;
+    let mut line = String::new();
+    let res = loop {
+        match event::read() {
+            Ok(Event::Key(key)) => match key.code {
+                KeyCode::Enter => break Ok(line),
+                KeyCode::Backspace => {
+                    line.pop();
+                }
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    break Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+                }
+                KeyCode::Esc => break Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled")),
+                KeyCode::Char(c) => line.push(c),
+                _ => (),
+            },
+            Ok(_) => (),
+            Err(e) => break Err(e),
+        }
+    };
+    disable_raw_mode()?;
+    println!();
+    res
+}
+
+fn usage() -> String {
+    String::from(
+        "usage: ctfterm login                    asks for username and password\n       \
+         ctfterm login --cookie <sessionid>\n       \
+         ctfterm logout",
+    )
+}
+
+/*
+ * run
+ * `ctfterm login` / `ctfterm logout`: save or forget the credentials,
+ * checking them against ctftime before they are saved
+ */
+pub async fn run(config: &Config, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let credentials = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["logout"] => {
+            clear();
+            println!("logged out");
+            return Ok(());
+        }
+        ["login", "--cookie", sessionid] => Credentials::Cookie { sessionid: sessionid.to_string() },
+        ["login"] => Credentials::Password {
+            username: prompt("username: ", false)?,
+            password: prompt("password: ", true)?,
+        },
+        _ => return Err(usage().into()),
+    };
+    let user = crate::loaders::load_login(credentials.clone()).await?;
+    let place = save(&config.account, &credentials)?;
+    println!("logged in as {}, saved to {}", user, place);
+    Ok(())
+}
@@ -0,0 +1,297 @@
+use crate::config::Config;
+use crate::types::{PastEvent, RunningEvent, TeamStat, UpcomingEvent, Writeup};
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, Connection, Transaction};
+use std::{collections::HashMap, error::Error};
+
+/*
+ * Store
+ * every fetched event, leaderboard and writeup kept in
+ * $XDG_DATA_HOME/ctfterm/history.db, refetched rows are merged instead of duplicated
+ */
+pub struct Store {
+    conn: Connection,
+}
+
+// one day of a team's leaderboard history
+pub struct RankDay {
+    pub day: String,
+    pub rank: u32,
+    pub points: f64,
+}
+
+// a team's place in an earlier leaderboard snapshot
+#[derive(Clone, Copy)]
+pub struct Standing {
+    pub rank: u32,
+    pub points: f64,
+}
+
+// which earlier snapshot to compare the current leaderboard with
+pub enum Since {
+    // the last day before today
+    Previous,
+    // the first day a snapshot of the year's leaderboard was taken
+    FirstOfYear,
+}
+
+pub struct EventRow {
+    pub kind: String,
+    pub title: String,
+    pub start: Option<String>,
+    pub last_seen: String,
+}
+
+pub struct WriteupRow {
+    pub event: String,
+    pub task: String,
+    pub author: String,
+    pub first_seen: String,
+}
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS events (
+        id INTEGER,
+        kind TEXT NOT NULL,
+        title TEXT NOT NULL,
+        start TEXT NOT NULL DEFAULT '',
+        finish TEXT NOT NULL DEFAULT '',
+        first_seen TEXT NOT NULL,
+        last_seen TEXT NOT NULL,
+        UNIQUE (kind, title, start)
+    );
+    CREATE TABLE IF NOT EXISTS rankings (
+        day TEXT NOT NULL,
+        fetched_at TEXT NOT NULL,
+        year INTEGER NOT NULL,
+        country TEXT NOT NULL,
+        team TEXT NOT NULL,
+        team_id INTEGER,
+        rank INTEGER NOT NULL,
+        points REAL NOT NULL,
+        UNIQUE (day, year, country, team)
+    );
+    CREATE TABLE IF NOT EXISTS writeups (
+        event TEXT NOT NULL,
+        task TEXT NOT NULL,
+        tags TEXT NOT NULL,
+        author TEXT NOT NULL,
+        first_seen TEXT NOT NULL,
+        UNIQUE (event, task, author)
+    );
+";
+
+fn stamp(date: DateTime<Utc>) -> String {
+    date.to_rfc3339()
+}
+
+impl Store {
+    pub fn open() -> Result<Store, Box<dyn Error>> {
+        let dir = Config::data_dir().ok_or("no data directory")?;
+        std::fs::create_dir_all(&dir)?;
+        let conn = Connection::open(dir.join("history.db"))?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Store { conn })
+    }
+
+    // one row, the record_* callers wrap their rows in a transaction
+    fn event(&self, kind: &str, id: Option<u32>, title: &str, start: Option<DateTime<Utc>>, finish: Option<DateTime<Utc>>) -> rusqlite::Result<()> {
+        let now = stamp(Utc::now());
+        self.conn.execute(
+            "INSERT INTO events (id, kind, title, start, finish, first_seen, last_seen)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+             ON CONFLICT (kind, title, start) DO UPDATE SET
+                id = COALESCE(excluded.id, id), finish = excluded.finish, last_seen = excluded.last_seen",
+            params![id, kind, title.trim(), start.map(stamp).unwrap_or_default(), finish.map(stamp).unwrap_or_default(), now],
+        )?;
+        Ok(())
+    }
+
+    // every record_* call is one transaction, a single sync instead of one per row.
+    // unchecked because the store is shared by reference, nothing else writes meanwhile
+    fn transaction(&self) -> rusqlite::Result<Transaction<'_>> {
+        self.conn.unchecked_transaction()
+    }
+
+    pub fn record_running(&self, events: &[RunningEvent]) -> rusqlite::Result<()> {
+        let tx = self.transaction()?;
+        for e in events
+        {
+            self.event("running", e.id, &e.title, e.start, e.finish)?;
+        }
+        tx.commit()
+    }
+
+    pub fn record_upcoming(&self, events: &[UpcomingEvent]) -> rusqlite::Result<()> {
+        let tx = self.transaction()?;
+        for e in events
+        {
+            self.event("upcoming", e.id, &e.title, Some(e.start), Some(e.finish))?;
+        }
+        tx.commit()
+    }
+
+    pub fn record_past(&self, events: &[PastEvent]) -> rusqlite::Result<()> {
+        let tx = self.transaction()?;
+        for e in events
+        {
+            self.event("past", e.id, &e.title, e.start, e.finish)?;
+        }
+        tx.commit()
+    }
+
+    // one snapshot per day and leaderboard, a later fetch on the same day replaces it
+    pub fn record_rankings(&self, year: i32, country: Option<&str>, stats: &[TeamStat]) -> rusqlite::Result<()> {
+        let now = Utc::now();
+        let day = now.format("%Y-%m-%d").to_string();
+        let tx = self.transaction()?;
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO rankings (day, fetched_at, year, country, team, team_id, rank, points)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                 ON CONFLICT (day, year, country, team) DO UPDATE SET
+                    fetched_at = excluded.fetched_at, team_id = excluded.team_id,
+                    rank = excluded.rank, points = excluded.points",
+            )?;
+            for s in stats
+            {
+                insert.execute(params![day, stamp(now), year, country.unwrap_or(""), s.name.trim(), s.team_id, s.position(), s.score()])?;
+            }
+        }
+        tx.commit()
+    }
+
+    pub fn record_writeups(&self, rows: &[Writeup]) -> rusqlite::Result<()> {
+        let now = stamp(Utc::now());
+        let tx = self.transaction()?;
+        {
+            let mut insert = tx.prepare(
+                "INSERT OR IGNORE INTO writeups (event, task, tags, author, first_seen) VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for (event, task, tags, author, _) in rows
+            {
+                insert.execute(params![event.trim(), task.trim(), tags.trim(), author.trim(), now])?;
+            }
+        }
+        tx.commit()
+    }
+
+    /*
+     * standings
+     * every team of an earlier snapshot of the same leaderboard, by name;
+     * empty when nothing was fetched before today
+     */
+    pub fn standings(&self, year: i32, country: Option<&str>, since: Since) -> rusqlite::Result<HashMap<String, Standing>> {
+        let pick = match since {
+            Since::Previous => "MAX",
+            Since::FirstOfYear => "MIN",
+        };
+        let today = Utc::now().format("%Y-%m-%d").to_string();
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT team, rank, points FROM rankings
+             WHERE year = ?1 AND country = ?2 AND day = (
+                SELECT {}(day) FROM rankings WHERE year = ?1 AND country = ?2 AND day < ?3
+             )",
+            pick,
+        ))?;
+        let rows = stmt.query_map(params![year, country.unwrap_or(""), today], |row| {
+            Ok((row.get::<_, String>(0)?, Standing { rank: row.get(1)?, points: row.get(2)? }))
+        })?;
+        rows.collect()
+    }
+
+    // a team's global rank per day over the last `days` days, matched by name
+    pub fn team_ranks(&self, team: &str, days: i64) -> rusqlite::Result<Vec<RankDay>> {
+        let since = (Utc::now() - Duration::days(days)).format("%Y-%m-%d").to_string();
+        let mut stmt = self.conn.prepare(
+            "SELECT day, rank, points FROM rankings
+             WHERE team = ?1 COLLATE NOCASE AND country = '' AND day >= ?2
+             ORDER BY day, year DESC",
+        )?;
+        let rows = stmt.query_map(params![team.trim(), since], |row| {
+            Ok(RankDay { day: row.get(0)?, rank: row.get(1)?, points: row.get(2)? })
+        })?;
+        let mut days: Vec<RankDay> = rows.collect::<rusqlite::Result<_>>()?;
+        // several years may be fetched on one day, keep the newest season
+        days.dedup_by(|b, a| a.day == b.day);
+        Ok(days)
+    }
+
+    // events seen in the last `days` days, newest first
+    pub fn events(&self, days: i64) -> rusqlite::Result<Vec<EventRow>> {
+        let since = stamp(Utc::now() - Duration::days(days));
+        let mut stmt = self.conn.prepare(
+            "SELECT kind, title, NULLIF(start, ''), last_seen FROM events WHERE last_seen >= ?1 ORDER BY start DESC",
+        )?;
+        let rows = stmt.query_map(params![since], |row| {
+            Ok(EventRow { kind: row.get(0)?, title: row.get(1)?, start: row.get(2)?, last_seen: row.get(3)? })
+        })?;
+        rows.collect()
+    }
+
+    // writeups first seen for events whose title contains `event`
+    pub fn writeups(&self, event: &str) -> rusqlite::Result<Vec<WriteupRow>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT event, task, author, first_seen FROM writeups
+             WHERE event LIKE '%' || ?1 || '%' ORDER BY first_seen DESC",
+        )?;
+        let rows = stmt.query_map(params![event.trim()], |row| {
+            Ok(WriteupRow { event: row.get(0)?, task: row.get(1)?, author: row.get(2)?, first_seen: row.get(3)? })
+        })?;
+        rows.collect()
+    }
+}
+
+fn usage() -> String {
+    String::from(
+        "usage: ctfterm history rank <team> [days]\n       \
+         ctfterm history events [days]\n       \
+         ctfterm history writeups [event]",
+    )
+}
+
+/*
+ * run
+ * `ctfterm history ...`: answer questions about earlier fetches from the command line
+ */
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let store = Store::open()?;
+    let days = |arg: Option<&String>| arg.and_then(|d| d.parse::<i64>().ok()).unwrap_or(30);
+    match args.first().map(String::as_str) {
+        Some("rank") => {
+            let team = args.get(1).ok_or_else(usage)?;
+            let ranks = store.team_ranks(team, days(args.get(2)))?;
+            if ranks.is_empty()
+            {
+                println!("no leaderboard snapshots of {}", team);
+            }
+            let mut prev: Option<u32> = None;
+            for r in &ranks
+            {
+                let delta = match prev {
+                    Some(p) if p > r.rank => format!("▲{}", p - r.rank),
+                    Some(p) if p < r.rank => format!("▼{}", r.rank - p),
+                    _ => String::new(),
+                };
+                println!("{}  #{:<5} {:>9.3}  {}", r.day, r.rank, r.points, delta);
+                prev = Some(r.rank);
+            }
+        }
+        Some("events") => {
+            for e in store.events(days(args.get(1)))?
+            {
+                let start = e.start.as_deref().map(|s| s.get(..16).unwrap_or(s)).unwrap_or("-");
+                println!("{:<8} {:<16}  {}  (last seen {})", e.kind, start, e.title, e.last_seen.get(..10).unwrap_or(&e.last_seen));
+            }
+        }
+        Some("writeups") => {
+            let event = args.get(1).map(String::as_str).unwrap_or("");
+            for w in store.writeups(event)?
+            {
+                println!("{}  {} - {} by {}", w.first_seen.get(..10).unwrap_or(&w.first_seen), w.event, w.task, w.author);
+            }
+        }
+        _ => return Err(usage().into()),
+    }
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use crossterm::{
+    cursor::Show,
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+// panics of background threads, shown in the status bar as they happen
+// and printed once the terminal is restored; the ui loop has seen the first SHOWN
+static PANICS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static SHOWN: AtomicUsize = AtomicUsize::new(0);
+
+pub fn enter() -> io::Result<()> {
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)
+}
+
+/*
+ * restore
+ * leave raw mode and the alternate screen, safe to call more than once
+ * and from a panic, so errors are ignored
+ */
+pub fn restore() {
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture, Show);
+}
+
+/*
+ * install_panic_hook
+ * a panic on the main thread restores the terminal before the message is printed,
+ * panics of other threads are kept for the status bar and printed once the terminal is restored
+ */
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if std::thread::current().name() == Some("main")
+        {
+            restore();
+            default_hook(info);
+            return;
+        }
+        let msg = match (info.payload().downcast_ref::<&str>(), info.payload().downcast_ref::<String>()) {
+            (Some(s), _) => s.to_string(),
+            (_, Some(s)) => s.clone(),
+            _ => String::from("panic"),
+        };
+        if let Ok(mut panics) = PANICS.lock()
+        {
+            panics.push(format!("background task panicked: {}", msg));
+        }
+    }));
+}
+
+// the panics since the last call, for the status bar
+pub fn take_panics() -> Vec<String> {
+    let panics = panics();
+    let shown = SHOWN.swap(panics.len(), Ordering::SeqCst);
+    panics.into_iter().skip(shown).collect()
+}
+
+// every panic of a background thread so far
+pub fn panics() -> Vec<String> {
+    PANICS.lock().map(|p| p.clone()).unwrap_or_default()
+}
+
+/*
+ * spawn_signal_handler
+ * set `quit` on SIGINT, SIGTERM or SIGHUP so the ui loop can shut down
+ * and restore the terminal like on 'q'
+ */
+pub fn spawn_signal_handler(quit: Arc<AtomicBool>) {
+    tokio::spawn(async move {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+            let (mut int, mut term, mut hup) = match (
+                signal(SignalKind::interrupt()),
+                signal(SignalKind::terminate()),
+                signal(SignalKind::hangup()),
+            ) {
+                (Ok(int), Ok(term), Ok(hup)) => (int, term, hup),
+                _ => return,
+            };
+            tokio::select! {
+                _ = int.recv() => (),
+                _ = term.recv() => (),
+                _ = hup.recv() => (),
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            if tokio::signal::ctrl_c().await.is_err()
+            {
+                return;
+            }
+        }
+        quit.store(true, Ordering::SeqCst);
+    });
+}
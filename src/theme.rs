@@ -0,0 +1,211 @@
+use crate::config::ThemeConfig;
+use tui::style::{Color, Modifier, Style};
+
+/*
+ * Theme
+ * colors used by every panel, picked from a built-in theme by name
+ * and optionally overridden color by color in the config
+ */
+#[derive(Clone)]
+pub struct Theme {
+    pub title: Color,
+    pub border: Color,
+    // border of the focused panel
+    pub focus: Color,
+    // background and text of the selected row
+    pub highlight: Color,
+    pub highlight_fg: Color,
+    pub header: Color,
+    // better / worse, e.g. won and lost shared events
+    pub positive: Color,
+    pub negative: Color,
+    pub toast: Color,
+    // one line color per team in the charts
+    pub series: [Color; 5],
+    // no colors at all, selection and focus are shown with modifiers
+    pub monochrome: bool,
+}
+
+impl Theme {
+    pub fn dark() -> Theme {
+        Theme {
+            title: Color::Red,
+            border: Color::Red,
+            focus: Color::White,
+            highlight: Color::LightGreen,
+            highlight_fg: Color::Reset,
+            header: Color::White,
+            positive: Color::LightGreen,
+            negative: Color::Red,
+            toast: Color::Yellow,
+            series: [Color::LightGreen, Color::Cyan, Color::Yellow, Color::Magenta, Color::LightBlue],
+            monochrome: false,
+        }
+    }
+
+    pub fn light() -> Theme {
+        Theme {
+            title: Color::Blue,
+            border: Color::Blue,
+            focus: Color::Black,
+            highlight: Color::Blue,
+            highlight_fg: Color::White,
+            header: Color::Black,
+            positive: Color::Green,
+            negative: Color::Red,
+            toast: Color::Magenta,
+            series: [Color::Green, Color::Blue, Color::Red, Color::Magenta, Color::Black],
+            monochrome: false,
+        }
+    }
+
+    pub fn high_contrast() -> Theme {
+        Theme {
+            title: Color::Yellow,
+            border: Color::White,
+            focus: Color::Yellow,
+            highlight: Color::Yellow,
+            highlight_fg: Color::Black,
+            header: Color::White,
+            positive: Color::LightGreen,
+            negative: Color::LightRed,
+            toast: Color::Yellow,
+            series: [Color::Yellow, Color::Cyan, Color::White, Color::LightMagenta, Color::LightGreen],
+            monochrome: false,
+        }
+    }
+
+    pub fn monochrome() -> Theme {
+        Theme {
+            title: Color::Reset,
+            border: Color::Reset,
+            focus: Color::Reset,
+            highlight: Color::Reset,
+            highlight_fg: Color::Reset,
+            header: Color::Reset,
+            positive: Color::Reset,
+            negative: Color::Reset,
+            toast: Color::Reset,
+            series: [Color::Reset; 5],
+            monochrome: true,
+        }
+    }
+
+    /*
+     * from_config
+     * the named built-in theme with the config's color overrides applied,
+     * a set NO_COLOR environment variable always wins and gives the monochrome theme
+     */
+    pub fn from_config(config: &ThemeConfig) -> Theme {
+        if std::env::var("NO_COLOR").map(|v| !v.is_empty()).unwrap_or(false)
+        {
+            return Theme::monochrome();
+        }
+
+        let mut theme = match config.name.to_ascii_lowercase().as_str() {
+            "light" => Theme::light(),
+            "high-contrast" | "high_contrast" => Theme::high_contrast(),
+            "monochrome" | "mono" => Theme::monochrome(),
+            _ => Theme::dark(),
+        };
+
+        for (name, value) in &config.colors {
+            let color = match parse_color(value) {
+                Some(color) => color,
+                None => continue,
+            };
+            match name.as_str() {
+                "title" => theme.title = color,
+                "border" => theme.border = color,
+                "focus" => theme.focus = color,
+                "highlight" => theme.highlight = color,
+                "highlight_fg" => theme.highlight_fg = color,
+                "header" => theme.header = color,
+                "positive" => theme.positive = color,
+                "negative" => theme.negative = color,
+                "toast" => theme.toast = color,
+                _ => {
+                    // series1 .. series5
+                    if let Some(i) = name.strip_prefix("series").and_then(|i| i.parse::<usize>().ok())
+                    {
+                        if (1..=theme.series.len()).contains(&i)
+                        {
+                            theme.series[i - 1] = color;
+                        }
+                    }
+                }
+            }
+        }
+        theme
+    }
+
+    pub fn title(&self) -> Style {
+        Style::default().fg(self.title).add_modifier(Modifier::BOLD)
+    }
+
+    pub fn border(&self, focused: bool) -> Style {
+        if !focused
+        {
+            return Style::default().fg(self.border);
+        }
+        let style = Style::default().fg(self.focus);
+        if self.monochrome { style.add_modifier(Modifier::BOLD) } else { style }
+    }
+
+    pub fn highlight(&self) -> Style {
+        if self.monochrome
+        {
+            return Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD);
+        }
+        Style::default()
+            .bg(self.highlight)
+            .fg(self.highlight_fg)
+            .add_modifier(Modifier::BOLD)
+    }
+
+    pub fn header(&self) -> Style {
+        Style::default().fg(self.header)
+    }
+
+    pub fn series(&self, i: usize) -> Color {
+        self.series[i % self.series.len()]
+    }
+}
+
+// "red", "lightblue", "#ff8800" or a 256 color index such as "208"
+pub fn parse_color(s: &str) -> Option<Color> {
+    let s = s.trim().to_ascii_lowercase().replace(['-', '_', ' '], "");
+    if let Some(hex) = s.strip_prefix('#')
+    {
+        if hex.len() != 6
+        {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+    if let Ok(idx) = s.parse::<u8>()
+    {
+        return Some(Color::Indexed(idx));
+    }
+    Some(match s.as_str() {
+        "reset" | "default" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => return None,
+    })
+}
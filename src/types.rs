@@ -1,17 +1,11 @@
-use std::{error::Error, io};
-use tui::{
-    backend::{Backend, CrosstermBackend},
-    layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
-    text::{Span,Spans},
-    widgets::{List, ListItem, ListState, Block, BorderType, Borders, Cell, Row, Table, TableState},
-    Frame, Terminal,
-};
+use chrono::{DateTime, Datelike, Duration, Utc};
+use crate::config::{Config, LayoutConfig, LeaderboardView, Panel};
+use std::{cmp::Ordering, collections::{HashMap, HashSet}, io, time::Instant};
+use tui::widgets::ListState;
 
 pub struct StatefulList<T> {
-    state: ListState,
+    pub state: ListState,
     pub items: Vec<T>,
-    idx: usize
 }
 
 impl<T: Clone> StatefulList<T> {
@@ -19,147 +13,1074 @@ impl<T: Clone> StatefulList<T> {
         StatefulList {
             state: ListState::default(),
             items,
-            idx: 0,
         }
     }
 
-    pub fn update(&mut self, items: &Vec<T>)
+    // reorder the items, the selection stays on the same item
+    pub fn sort_by(&mut self, mut compare: impl FnMut(&T, &T) -> Ordering)
     {
-        let mut i = 0;
-        while i < items.len()
-        {
-            self.items.push(items[i].clone());
-            i+=1;
-        }
+        let selected = self.state.selected();
+        let mut tagged: Vec<(usize, T)> = self.items.drain(..).enumerate().collect();
+        tagged.sort_by(|(_, a), (_, b)| compare(a, b));
+        self.state.select(selected.and_then(|old| tagged.iter().position(|(i, _)| *i == old)));
+        self.items = tagged.into_iter().map(|(_, item)| item).collect();
     }
 
-    pub fn scroll(&mut self) {
-        self.items.push(self.items[0].clone());
-        self.items.remove(0);
+    pub fn update(&mut self, items: &[T])
+    {
+        self.items.extend_from_slice(items);
+    }
+
+    // swap in a fresh set of items, dropping the old selection
+    pub fn replace(&mut self, items: Vec<T>)
+    {
+        self.items = items;
+        self.state.select(None);
     }
 
     pub fn move_down(&mut self){
-        if self.idx < self.items.len()-1
+        if self.items.is_empty()
         {
-            self.items.push(self.items[0].clone());
-            self.items.remove(0);
-            self.idx += 1;
+            return;
+        }
+        let i = match self.state.selected() {
+            Some(i) => (i + 1).min(self.items.len() - 1),
+            None => 0,
+        };
+        self.state.select(Some(i));
+    }
+
+    pub fn move_up(&mut self){
+        if let Some(i) = self.state.selected()
+        {
+            self.state.select(Some(i.saturating_sub(1)));
         }
     }
 
-    pub fn get(&mut self, index: usize) -> &T
+    // swap in refreshed items, keeping the selection where it was when possible
+    pub fn refresh(&mut self, items: Vec<T>)
     {
-        return &self.items[index]; 
+        self.items = items;
+        match self.state.selected() {
+            Some(_) if self.items.is_empty() => self.state.select(None),
+            Some(i) => self.state.select(Some(i.min(self.items.len() - 1))),
+            None => (),
+        }
     }
 
-    pub fn set(&mut self, data: T, index: usize)
+    // number of rows below the current selection
+    pub fn remaining(&self) -> usize
     {
-        self.items[index] = data;
+        let selected = self.state.selected().unwrap_or(0);
+        self.items.len().saturating_sub(selected + 1)
     }
+}
 
-    pub fn move_up(&mut self){
-        if self.idx > 0
+const PAGE_RETRY: std::time::Duration = std::time::Duration::from_secs(30);
+
+/*
+ * Pager
+ * tracks how many pages of a paginated ctftime listing
+ * have been appended to a panel and whether another is in flight
+ */
+pub struct Pager {
+    // last page appended, 0 until the first one arrives
+    pub page: u32,
+    pub max_pages: u32,
+    pub loading: bool,
+    pub exhausted: bool,
+    // no request before this after a failed fetch
+    pub retry_at: Option<Instant>,
+}
+
+impl Pager {
+    pub fn new(max_pages: u32) -> Pager {
+        Pager {
+            page: 0,
+            max_pages,
+            loading: false,
+            exhausted: false,
+            retry_at: None,
+        }
+    }
+
+    // whether the next page should be requested given the rows left below the selection
+    pub fn wants_next(&self, remaining: usize, prefetch_rows: usize) -> bool {
+        !self.loading && !self.exhausted && self.page < self.max_pages && remaining <= prefetch_rows
+            && self.retry_at.is_none_or(|at| Instant::now() >= at)
+    }
+
+    // mark the next page as in flight and return its number
+    pub fn begin(&mut self) -> u32 {
+        self.loading = true;
+        self.page + 1
+    }
+
+    pub fn finish(&mut self, page: u32, count: usize) {
+        self.loading = false;
+        self.retry_at = None;
+        if count == 0
         {
-            self.items.insert(0, self.items[self.items.len()-1].clone());
-            self.items.remove(self.items.len()-1);
-            self.idx -= 1;
+            self.exhausted = true;
+        }
+        else
+        {
+            self.page = page;
         }
     }
 
-    pub fn next(&mut self) {
-        let i = match self.state.selected() {
-            Some(i) => {
-                if i >= self.items.len() - 1 {
-                    0
-                } else {
-                    i + 1
-                }
-            }
-            None => 0,
-        };
-        self.state.select(Some(i));
+    // a failed fetch is retried after a pause instead of on every frame
+    pub fn fail(&mut self) {
+        self.loading = false;
+        self.retry_at = Some(Instant::now() + PAGE_RETRY);
     }
+}
 
-    pub fn previous(&mut self) {
-        let i = match self.state.selected() {
-            Some(i) => {
-                if i == 0 {
-                    self.items.len() - 1
-                } else {
-                    i - 1
-                }
-            }
-            None => 0,
-        };
-        self.state.select(Some(i));
+// (event, task, tags, author, action) row of the writeups listing
+pub type Writeup = (String, String, String, String, String);
+
+// an event from the running feed
+#[derive(Clone)]
+pub struct RunningEvent {
+    // ctftime event id, when the feed links the event page
+    pub id: Option<u32>,
+    pub title: String,
+    pub start: Option<DateTime<Utc>>,
+    pub finish: Option<DateTime<Utc>>,
+    pub format: String,
+    pub weight: f64,
+    pub organizers: Vec<String>,
+}
+
+impl RunningEvent {
+    pub fn remaining(&self, now: DateTime<Utc>) -> Option<Duration> {
+        self.finish.map(|finish| (finish - now).max(Duration::zero()))
     }
 
-    pub fn unselect(&mut self) {
-        self.state.select(None);
+    // how far through the event we are, 0.0 to 1.0
+    pub fn progress(&self, now: DateTime<Utc>) -> Option<f64> {
+        let (start, finish) = (self.start?, self.finish?);
+        let total = (finish - start).num_seconds();
+        if total <= 0
+        {
+            return None;
+        }
+        Some(((now - start).num_seconds() as f64 / total as f64).clamp(0.0, 1.0))
+    }
+}
+
+// a finished event from the past events list or feed
+#[derive(Clone)]
+pub struct PastEvent {
+    pub id: Option<u32>,
+    pub title: String,
+    pub start: Option<DateTime<Utc>>,
+    pub finish: Option<DateTime<Utc>>,
+    // ctftime's text, shown when it could not be parsed
+    pub date: String,
+    pub weight: Option<f64>,
+    pub participants: Option<u32>,
+}
+
+// everything ctftime tells about one event, shown when drilling into it
+#[derive(Clone)]
+pub struct EventDetail {
+    pub id: u32,
+    pub title: String,
+    pub start: Option<DateTime<Utc>>,
+    pub finish: Option<DateTime<Utc>>,
+    pub format: String,
+    pub weight: f64,
+    pub onsite: bool,
+    pub location: String,
+    pub url: String,
+    pub participants: u32,
+    pub organizers: Vec<String>,
+    pub description: String,
+}
+
+// an event that has not started yet
+#[derive(Clone)]
+pub struct UpcomingEvent {
+    // None for feed entries that link no ctftime event
+    pub id: Option<u32>,
+    pub title: String,
+    pub start: DateTime<Utc>,
+    pub finish: DateTime<Utc>,
+    pub format: String,
+    pub weight: f64,
+    pub onsite: bool,
+}
+
+impl UpcomingEvent {
+    // the ctftime id, or the title and start for an entry without one
+    pub fn key(&self) -> String {
+        match self.id {
+            Some(id) => id.to_string(),
+            None => format!("{}@{}", self.title, self.start.timestamp()),
+        }
+    }
+}
+
+// one row of the leaderboard
+#[derive(Clone)]
+pub struct TeamStat {
+    pub rank: String,
+    pub name: String,
+    pub points: String,
+    // country name, or ctftime's text when there was no flag
+    pub country: String,
+    pub country_code: Option<String>,
+    pub team_id: Option<u32>,
+}
+
+fn number(s: &str) -> f64 {
+    s.trim().trim_start_matches('#').replace(',', "").parse().unwrap_or(0.0)
+}
+
+impl TeamStat {
+    pub fn position(&self) -> u32 {
+        number(&self.rank) as u32
+    }
+
+    pub fn score(&self) -> f64 {
+        number(&self.points)
+    }
+}
+
+// leaderboard columns it can be sorted by, in the order 'o' cycles through them
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TeamColumn {
+    Rank,
+    // places climbed since the first snapshot of the year
+    Movers,
+    Name,
+    Points,
+    Country,
+}
+
+impl TeamColumn {
+    pub const ALL: [TeamColumn; 5] = [TeamColumn::Rank, TeamColumn::Movers, TeamColumn::Name, TeamColumn::Points, TeamColumn::Country];
+}
+
+// which teams the leaderboard shows by their academic status, in the order 'e' cycles through
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AcademicFilter {
+    All,
+    Academic,
+    NonAcademic,
+}
+
+impl AcademicFilter {
+    pub fn next(self) -> AcademicFilter {
+        match self {
+            AcademicFilter::All => AcademicFilter::Academic,
+            AcademicFilter::Academic => AcademicFilter::NonAcademic,
+            AcademicFilter::NonAcademic => AcademicFilter::All,
+        }
+    }
+
+    // teams whose status isn't known yet stay hidden until it is
+    pub fn shows(self, academic: Option<bool>) -> bool {
+        match self {
+            AcademicFilter::All => true,
+            AcademicFilter::Academic => academic == Some(true),
+            AcademicFilter::NonAcademic => academic == Some(false),
+        }
+    }
+
+    pub fn label(self) -> Option<&'static str> {
+        match self {
+            AcademicFilter::All => None,
+            AcademicFilter::Academic => Some("academic"),
+            AcademicFilter::NonAcademic => Some("non-academic"),
+        }
+    }
+}
+
+// past event columns it can be sorted by, in the order 'o' cycles through them
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EventColumn {
+    Date,
+    Name,
+    Weight,
+    Participants,
+}
+
+impl EventColumn {
+    pub const ALL: [EventColumn; 4] = [EventColumn::Date, EventColumn::Name, EventColumn::Weight, EventColumn::Participants];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EventColumn::Date => "date",
+            EventColumn::Name => "name",
+            EventColumn::Weight => "weight",
+            EventColumn::Participants => "teams",
+        }
+    }
+}
+
+// the column a list is sorted by and in which direction
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Sort<C> {
+    pub column: C,
+    pub descending: bool,
+}
+
+impl<C: Copy + PartialEq> Sort<C> {
+    // ascending, then descending, then the next column ascending
+    pub fn next(self, columns: &[C]) -> Sort<C> {
+        if !self.descending
+        {
+            return Sort { column: self.column, descending: true };
+        }
+        let i = columns.iter().position(|c| *c == self.column).unwrap_or(0);
+        Sort { column: columns[(i + 1) % columns.len()], descending: false }
+    }
+
+    pub fn arrow(&self) -> &'static str {
+        if self.descending { "↓" } else { "↑" }
+    }
+
+    fn apply(&self, ord: Ordering) -> Ordering {
+        if self.descending { ord.reverse() } else { ord }
+    }
+}
+
+// compare two values that may be missing, and whether the sort direction applies:
+// missing values go last in either direction
+fn known<T: PartialOrd>(a: Option<T>, b: Option<T>) -> (Ordering, bool) {
+    match (a, b) {
+        (Some(a), Some(b)) => (a.partial_cmp(&b).unwrap_or(Ordering::Equal), true),
+        (Some(_), None) => (Ordering::Less, false),
+        (None, Some(_)) => (Ordering::Greater, false),
+        (None, None) => (Ordering::Equal, false),
+    }
+}
+
+/*
+ * compare_teams
+ * leaderboard order for a sort, ties in rank order; teams missing from the first
+ * snapshot of the year have no movement and go last like other missing values
+ */
+fn compare_teams(sort: Sort<TeamColumn>, first: &HashMap<String, crate::store::Standing>, a: &TeamStat, b: &TeamStat) -> Ordering {
+    let climbed = |s: &TeamStat| match App::movement(s, first) {
+        Some(Movement::Moved { places, .. }) => Some(places),
+        Some(Movement::New) => None,
+        None => Some(0),
+    };
+    let (ord, sorted) = match sort.column {
+        TeamColumn::Rank => (a.position().cmp(&b.position()), true),
+        TeamColumn::Movers => known(climbed(a), climbed(b)),
+        TeamColumn::Name => (a.name.to_lowercase().cmp(&b.name.to_lowercase()), true),
+        TeamColumn::Points => known(Some(a.score()), Some(b.score())),
+        TeamColumn::Country => (a.country.to_lowercase().cmp(&b.country.to_lowercase()), true),
+    };
+    let ord = if sorted { sort.apply(ord) } else { ord };
+    ord.then(a.position().cmp(&b.position()))
+}
+
+// how a team moved since an earlier leaderboard snapshot
+#[derive(Clone, Copy, PartialEq)]
+pub enum Movement {
+    New,
+    // places climbed, negative when the team dropped
+    Moved { places: i64, points: f64 },
+}
+
+// a team's placement in a single event
+#[derive(Clone)]
+pub struct EventResult {
+    pub year: i32,
+    pub event_id: Option<u32>,
+    pub event: String,
+    pub place: u32,
+    pub rating_points: f64,
+}
+
+// one team on the final scoreboard of an event
+#[derive(Clone)]
+pub struct ScoreRow {
+    pub place: u32,
+    pub team: String,
+    pub team_id: Option<u32>,
+    // ctf points, the rating points are computed from them
+    pub points: f64,
+}
+
+// an event on the logged in member's panels
+#[derive(Clone)]
+pub struct AccountEvent {
+    pub id: u32,
+    pub title: String,
+    pub start: Option<DateTime<Utc>>,
+}
+
+// what only a logged in member sees: events the team registered for and weight votes still open
+#[derive(Clone, Default)]
+pub struct Account {
+    pub registrations: Vec<AccountEvent>,
+    pub votes: Vec<AccountEvent>,
+}
+
+// a team's rating over the years plus every event result listed on its page
+#[derive(Clone)]
+pub struct TeamHistory {
+    pub id: u32,
+    pub name: String,
+    pub country: String,
+    pub academic: bool,
+    // (year, rating points, global rank, country rank) sorted by year
+    pub years: Vec<(i32, f64, Option<u32>, Option<u32>)>,
+    // oldest first
+    pub results: Vec<EventResult>,
+}
+
+impl TeamHistory {
+    pub fn season(&self, year: i32) -> Vec<&EventResult> {
+        self.results.iter().filter(|r| r.year == year).collect()
+    }
+}
+
+/*
+ * Comparison
+ * two teams side by side with the events both of them played
+ */
+pub struct Comparison {
+    pub a: TeamHistory,
+    pub b: TeamHistory,
+    // (event, place of a, place of b) oldest first
+    pub shared: Vec<(String, u32, u32)>,
+    pub wins: usize,
+    pub losses: usize,
+}
+
+impl Comparison {
+    pub fn new(a: TeamHistory, b: TeamHistory) -> Comparison {
+        let shared: Vec<(String, u32, u32)> = a.results.iter()
+            .filter_map(|ra| {
+                let rb = b.results.iter().find(|rb| match (ra.event_id, rb.event_id) {
+                    (Some(x), Some(y)) => x == y,
+                    _ => ra.year == rb.year && ra.event == rb.event,
+                })?;
+                Some((ra.event.clone(), ra.place, rb.place))
+            })
+            .collect();
+        let wins = shared.iter().filter(|(_, pa, pb)| pa < pb).count();
+        let losses = shared.iter().filter(|(_, pa, pb)| pa > pb).count();
+        Comparison { a, b, shared, wins, losses }
+    }
+}
+
+// what the large panel right of the leaderboard shows
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MainView {
+    PastEvents,
+    Charts,
+    Compare,
+    Upcoming,
+    // the week's events we play or might play
+    Schedule,
+}
+
+/*
+ * Loaded
+ * results sent back to the ui loop from background fetches
+ */
+pub enum Loaded {
+    PastEvents(u32, io::Result<Vec<PastEvent>>),
+    Writeups(u32, io::Result<Vec<Writeup>>),
+    // the year and country the leaderboard was requested for
+    Leaderboard {
+        year: i32,
+        country: Option<String>,
+        res: io::Result<Vec<TeamStat>>,
+    },
+    TeamHistory(io::Result<TeamHistory>),
+    Comparison(io::Result<Comparison>),
+    EventDetail(u32, io::Result<EventDetail>),
+    Scoreboard(u32, io::Result<Vec<ScoreRow>>),
+    TeamDetail(u32, io::Result<TeamHistory>),
+    // whether a leaderboard team is academic, for the academic filter
+    Academic(u32, io::Result<bool>),
+    // the logged in user, then their account panels
+    Login(io::Result<String>),
+    Account(io::Result<Account>),
+    // periodic refresh of the feeds watched for notifications
+    Refresh {
+        running: io::Result<Vec<RunningEvent>>,
+        upcoming: io::Result<Vec<UpcomingEvent>>,
+        writeups: io::Result<Vec<Writeup>>,
+    },
+}
+
+impl Loaded {
+    // (source name, error) for every fetch this result reports on, for the status bar
+    pub fn outcomes(&self) -> Vec<(&'static str, Option<String>)> {
+        fn outcome<T>(source: &'static str, res: &io::Result<T>) -> (&'static str, Option<String>) {
+            (source, res.as_ref().err().map(|e| format!("{}: {}", source, e)))
+        }
+        match self {
+            Loaded::PastEvents(_, res) => vec![outcome("past events", res)],
+            Loaded::Writeups(_, res) => vec![outcome("writeups", res)],
+            Loaded::Leaderboard { res, .. } => vec![outcome("leaderboard", res)],
+            Loaded::TeamHistory(res) | Loaded::TeamDetail(_, res) => vec![outcome("teams", res)],
+            Loaded::Comparison(res) => vec![outcome("teams", res)],
+            Loaded::Academic(_, res) => vec![outcome("teams", res)],
+            Loaded::EventDetail(_, res) => vec![outcome("events", res)],
+            Loaded::Scoreboard(_, res) => vec![outcome("events", res)],
+            Loaded::Login(res) => vec![outcome("account", res)],
+            Loaded::Account(res) => vec![outcome("account", res)],
+            Loaded::Refresh { running, upcoming, writeups } => vec![
+                outcome("running", running),
+                outcome("upcoming", upcoming),
+                outcome("writeups", writeups),
+            ],
+        }
     }
 }
 
-pub struct App<'a> {
-    pub focused: i16,
-    pub term_height: u16,
-    pub curr_events: StatefulList<String>,
-    pub current_events_list: StatefulList<String>,
-    pub past_events_list: StatefulList<(String, String)>,
-    pub leaderboard_stats: StatefulList<(String, String, String, String)>,
-    pub writeups: StatefulList<(String, String, String, String, String)>,
-    pub events: Vec<(&'a str, &'a str)>,
+pub const MAX_CHART_TEAMS: usize = 5;
+
+// ctftime's rating starts with this year
+pub const FIRST_RATING_YEAR: i32 = 2011;
+
+// the screens selectable in the tab bar
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Tab {
+    Dashboard,
+    Events,
+    Leaderboard,
+    Writeups,
+    Watchlist,
+    MyTeam,
 }
 
-impl<'a> App<'a> {
-    pub fn new() -> App<'a> {
+impl Tab {
+    pub const ALL: [Tab; 6] = [Tab::Dashboard, Tab::Events, Tab::Leaderboard, Tab::Writeups, Tab::Watchlist, Tab::MyTeam];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Tab::Dashboard => "Dashboard",
+            Tab::Events => "Events",
+            Tab::Leaderboard => "Leaderboard",
+            Tab::Writeups => "Writeups",
+            Tab::Watchlist => "Watchlist",
+            Tab::MyTeam => "My Team",
+        }
+    }
+
+    // list panels shown left of the detail view, the dashboard uses the layout instead
+    pub fn panels(&self) -> &'static [Panel] {
+        match self {
+            Tab::Dashboard => &[],
+            Tab::Events => &[Panel::Running, Panel::Main],
+            Tab::Leaderboard => &[Panel::Leaderboard],
+            Tab::Writeups => &[Panel::Writeups],
+            Tab::Watchlist => &[Panel::Watchlist],
+            Tab::MyTeam => &[],
+        }
+    }
+}
+
+// something drilled into from a list
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Detail {
+    Event(u32),
+    Team(u32),
+}
+
+/*
+ * History
+ * back / forward navigation through the details drilled into,
+ * no current detail means the selected row is previewed
+ */
+#[derive(Default)]
+pub struct History {
+    back: Vec<Detail>,
+    pub current: Option<Detail>,
+    forward: Vec<Detail>,
+}
+
+impl History {
+    pub fn push(&mut self, detail: Detail) {
+        if self.current == Some(detail)
+        {
+            return;
+        }
+        if let Some(current) = self.current.take()
+        {
+            self.back.push(current);
+        }
+        self.current = Some(detail);
+        self.forward.clear();
+    }
+
+    pub fn back(&mut self) {
+        if self.current.is_none()
+        {
+            return;
+        }
+        if let Some(current) = self.current.take()
+        {
+            self.forward.push(current);
+        }
+        self.current = self.back.pop();
+    }
+
+    pub fn forward(&mut self) {
+        if let Some(next) = self.forward.pop()
+        {
+            if let Some(current) = self.current.take()
+            {
+                self.back.push(current);
+            }
+            self.current = Some(next);
+        }
+    }
+}
+
+pub struct App {
+    pub config: Config,
+    pub theme: crate::theme::Theme,
+    pub clock: crate::dates::Clock,
+    pub focused: Panel,
+    // the focused panel takes the whole screen
+    pub maximized: bool,
+    pub tab: Tab,
+    pub history: History,
+    // details fetched so far, by ctftime id
+    pub events: HashMap<u32, EventDetail>,
+    pub scoreboards: HashMap<u32, Vec<ScoreRow>>,
+    // our hypothetical placement in the upcoming event opened
+    pub what_if: crate::rating::WhatIf,
+    pub teams: HashMap<u32, TeamHistory>,
+    // results of the configured team, listed in the My Team tab
+    pub my_team: StatefulList<EventResult>,
+    pub show_help: bool,
+    // last successful fetch per source, and the sources whose last fetch failed
+    pub refreshed: HashMap<&'static str, DateTime<Utc>>,
+    pub failing: HashSet<&'static str>,
+    // last fetch error until dismissed
+    pub error: Option<String>,
+    pub curr_events: StatefulList<RunningEvent>,
+    // characters the compact ticker has scrolled by
+    pub ticker_offset: usize,
+    pub past_events_list: StatefulList<PastEvent>,
+    pub past_events_pager: Pager,
+    pub leaderboard_stats: StatefulList<TeamStat>,
+    // the same leaderboard on the previous fetched day and in the first snapshot of the year
+    pub previous_standings: HashMap<String, crate::store::Standing>,
+    pub first_standings: HashMap<String, crate::store::Standing>,
+    pub leaderboard_sort: Sort<TeamColumn>,
+    // country code and academic status the leaderboard is narrowed to, and the teams left out meanwhile
+    pub country_filter: Option<String>,
+    pub academic_filter: AcademicFilter,
+    pub leaderboard_hidden: Vec<TeamStat>,
+    // academic status by team id as far as it was looked up, and the lookups in flight
+    pub academic: HashMap<u32, bool>,
+    pub academic_pending: HashSet<u32>,
+    pub past_events_sort: Sort<EventColumn>,
+    // teams plotted in the chart view, at most MAX_CHART_TEAMS
+    pub chart_teams: Vec<TeamHistory>,
+    pub main_view: MainView,
+    // team picked as the first side of a comparison, waiting for the second
+    pub compare_pick: Option<u32>,
+    pub comparison: Option<Comparison>,
+    pub writeups: StatefulList<Writeup>,
+    pub writeups_pager: Pager,
+    pub upcoming: StatefulList<UpcomingEvent>,
+    // event titles followed for notifications, mirrors config.watchlist
+    pub watchlist: StatefulList<String>,
+    pub watcher: crate::notify::Watcher,
+    pub registrations: crate::schedule::Registrations,
+    // ctftime user we are logged in as, and their account panels once loaded
+    pub user: Option<String>,
+    pub account: Option<Account>,
+    // local history database, None when it could not be opened
+    pub store: Option<crate::store::Store>,
+    // weeks from the current one shown by the schedule
+    pub week_offset: i64,
+    pub toasts: Vec<crate::notify::Toast>,
+}
+
+impl App {
+    pub fn new(mut config: Config) -> App {
+        // a layout without any panel left would be an empty screen
+        if config.layout.visible().is_empty()
+        {
+            config.layout = LayoutConfig::default();
+        }
         App {
-            focused: 0,
-            term_height: 0,
+            theme: crate::theme::Theme::from_config(&config.theme),
+            clock: crate::dates::Clock::from_config(&config.time),
+            focused: config.layout.visible()[0],
+            maximized: false,
+            tab: Tab::Dashboard,
+            history: History::default(),
+            events: HashMap::new(),
+            scoreboards: HashMap::new(),
+            what_if: crate::rating::WhatIf::default(),
+            teams: HashMap::new(),
+            my_team: StatefulList::with_items(vec![]),
+            show_help: false,
+            refreshed: HashMap::new(),
+            failing: HashSet::new(),
+            error: None,
             curr_events: StatefulList::with_items(vec![
             ]),
-            current_events_list: StatefulList::with_items(vec![
-            ]),
+            ticker_offset: 0,
             past_events_list: StatefulList::with_items(vec![
             ]),
+            past_events_pager: Pager::new(config.pagination.max_pages),
             leaderboard_stats: StatefulList::with_items(vec![
             ]),
+            previous_standings: HashMap::new(),
+            first_standings: HashMap::new(),
+            leaderboard_sort: Sort { column: TeamColumn::Rank, descending: false },
+            country_filter: None,
+            academic_filter: AcademicFilter::All,
+            leaderboard_hidden: vec![],
+            academic: HashMap::new(),
+            academic_pending: HashSet::new(),
+            // newest first like ctftime lists them
+            past_events_sort: Sort { column: EventColumn::Date, descending: true },
+            chart_teams: vec![],
+            main_view: MainView::PastEvents,
+            compare_pick: None,
+            comparison: None,
             writeups: StatefulList::with_items(vec![
             ]),
-            events: vec![
-                ("Event1", "INFO"),
-                ("Event2", "INFO"),
-                ("Event3", "CRITICAL"),
-                ("Event4", "ERROR"),
-                ("Event5", "INFO"),
-                ("Event6", "INFO"),
-                ("Event7", "WARNING"),
-                ("Event8", "INFO"),
-                ("Event9", "INFO"),
-                ("Event10", "INFO"),
-                ("Event11", "CRITICAL"),
-                ("Event12", "INFO"),
-                ("Event13", "INFO"),
-                ("Event14", "INFO"),
-                ("Event15", "INFO"),
-                ("Event16", "INFO"),
-                ("Event17", "ERROR"),
-                ("Event18", "ERROR"),
-                ("Event19", "INFO"),
-                ("Event20", "INFO"),
-                ("Event21", "WARNING"),
-                ("Event22", "INFO"),
-                ("Event23", "INFO"),
-                ("Event24", "WARNING"),
-                ("Event25", "INFO"),
-                ("Event26", "INFO"),
-            ],
+            writeups_pager: Pager::new(config.pagination.max_pages),
+            upcoming: StatefulList::with_items(vec![
+            ]),
+            watchlist: StatefulList::with_items(config.watchlist.clone()),
+            watcher: crate::notify::Watcher::default(),
+            registrations: crate::schedule::Registrations::load(),
+            user: None,
+            account: None,
+            store: crate::store::Store::open().ok(),
+            week_offset: 0,
+            toasts: vec![],
+            config,
+        }
+    }
+
+    // every running title joined for the compact ticker
+    pub fn ticker_text(&self) -> String {
+        let mut s = String::new();
+        for evt in &self.curr_events.items {
+            s.push_str(" | ");
+            s.push_str(&evt.title);
+        }
+        if s.is_empty()
+        {
+            s.push_str(" | None");
+        }
+        s
+    }
+
+    pub fn is_watched(&self, title: &str) -> bool {
+        self.watchlist.items.iter().any(|w| w.eq_ignore_ascii_case(title.trim()))
+    }
+
+    // follow or unfollow an event title and remember the watchlist
+    pub fn toggle_watch(&mut self, title: &str) {
+        let title = title.trim();
+        if title.is_empty()
+        {
+            return;
+        }
+        match self.watchlist.items.iter().position(|w| w.eq_ignore_ascii_case(title)) {
+            Some(idx) => {
+                self.watchlist.items.remove(idx);
+                self.watchlist.state.select(None);
+            }
+            None => self.watchlist.items.push(title.to_string()),
+        }
+        self.config.watchlist = self.watchlist.items.clone();
+        self.save_state();
+    }
+
+    // remember the selections made in the ui for the next start
+    pub fn save_state(&mut self) {
+        if let Err(e) = crate::config::UiState::of(&self.config).save()
+        {
+            self.error = Some(format!("state: {}", e));
         }
     }
 
-    /// Rotate through the event list.
-    /// This only exists to simulate some kind of "progress"
-    pub fn on_tick(&mut self) {
-        let event = self.events.remove(0);
-        self.events.push(event);
+    // note the outcome of a fetch for the status bar
+    pub fn record(&mut self, source: &'static str, error: Option<String>) {
+        match error {
+            Some(e) => {
+                self.failing.insert(source);
+                self.error = Some(e);
+            }
+            None => {
+                self.failing.remove(source);
+                self.refreshed.insert(source, Utc::now());
+            }
+        }
+    }
+
+    // every source tried so far failed its last fetch
+    pub fn offline(&self) -> bool {
+        !self.failing.is_empty() && self.refreshed.keys().all(|s| self.failing.contains(s))
+    }
+
+    pub fn leaderboard_year(&self) -> i32 {
+        let now = Utc::now().year();
+        self.config.leaderboard.year.unwrap_or(now).clamp(FIRST_RATING_YEAR, now)
+    }
+
+    // move the leaderboard a year back or ahead, false when that year has no rating
+    pub fn step_leaderboard_year(&mut self, by: i32) -> bool {
+        let year = self.leaderboard_year() + by;
+        if !(FIRST_RATING_YEAR..=Utc::now().year()).contains(&year)
+        {
+            return false;
+        }
+        self.config.leaderboard.year = Some(year);
+        true
+    }
+
+    // country code to narrow the leaderboard to, None for the global table
+    pub fn leaderboard_country(&self) -> Option<String> {
+        match self.config.leaderboard.view {
+            LeaderboardView::Global => None,
+            LeaderboardView::Country => Some(self.config.leaderboard.country.clone()),
+        }
+    }
+
+    pub fn leaderboard_title(&self) -> String {
+        let title = match self.leaderboard_country() {
+            Some(code) => format!("Leaderboard {} [{}]", self.leaderboard_year(), code),
+            None => format!("Leaderboard {} [Global]", self.leaderboard_year()),
+        };
+        let title = match &self.country_filter {
+            Some(code) => format!("{} [{} only]", title, code),
+            None => title,
+        };
+        match self.academic_filter.label() {
+            Some(label) => format!("{} [{}]", title, label),
+            None => title,
+        }
+    }
+
+    /*
+     * movement
+     * change of a team since an earlier snapshot, None without one
+     * to compare with so a first fetch doesn't mark every team new
+     */
+    pub fn movement(stat: &TeamStat, since: &HashMap<String, crate::store::Standing>) -> Option<Movement> {
+        if since.is_empty()
+        {
+            return None;
+        }
+        Some(match since.get(stat.name.trim()) {
+            Some(old) => Movement::Moved {
+                places: old.rank as i64 - stat.position() as i64,
+                points: stat.score() - old.points,
+            },
+            None => Movement::New,
+        })
+    }
+
+    // look up the earlier snapshots of the shown leaderboard and apply the sort order
+    pub fn leaderboard_loaded(&mut self) {
+        let (year, country) = (self.leaderboard_year(), self.leaderboard_country());
+        if let Some(store) = &self.store
+        {
+            use crate::store::Since;
+            match (
+                store.standings(year, country.as_deref(), Since::Previous),
+                store.standings(year, country.as_deref(), Since::FirstOfYear),
+            ) {
+                (Ok(previous), Ok(first)) => {
+                    self.previous_standings = previous;
+                    self.first_standings = first;
+                }
+                (Err(e), _) | (_, Err(e)) => self.error = Some(format!("history: {}", e)),
+            }
+        }
+        // teams hidden by the filter belong to the previous fetch
+        self.leaderboard_hidden.clear();
+        self.filter_leaderboard();
+    }
+
+    // show only the teams of one country, None shows every team again
+    pub fn filter_country(&mut self, code: Option<String>) {
+        self.country_filter = code;
+        self.filter_leaderboard();
+    }
+
+    // show only the teams passing the country and academic filters
+    pub fn filter_leaderboard(&mut self) {
+        let list = &mut self.leaderboard_stats;
+        let selected = list.state.selected().and_then(|i| list.items.get(i)).map(|s| s.name.clone());
+        list.items.append(&mut self.leaderboard_hidden);
+        let (code, academic, known) = (&self.country_filter, self.academic_filter, &self.academic);
+        let (shown, hidden) = list.items.drain(..).partition(|s| {
+            code.as_ref().is_none_or(|code| s.country_code.as_ref() == Some(code))
+                && academic.shows(s.team_id.and_then(|id| known.get(&id).copied()))
+        });
+        list.items = shown;
+        self.leaderboard_hidden = hidden;
+        list.state.select(selected.and_then(|name| list.items.iter().position(|s| s.name == name)));
+        self.sort_leaderboard();
+    }
+
+    // by the chosen column, see compare_teams
+    pub fn sort_leaderboard(&mut self) {
+        let sort = self.leaderboard_sort;
+        let first = &self.first_standings;
+        self.leaderboard_stats.sort_by(|a, b| compare_teams(sort, first, a, b));
+    }
+
+    // by the chosen column, events missing the value last
+    pub fn sort_past_events(&mut self) {
+        let sort = self.past_events_sort;
+        self.past_events_list.sort_by(|a, b| {
+            let (ord, sorted) = match sort.column {
+                EventColumn::Date => known(a.start, b.start),
+                EventColumn::Name => (a.title.to_lowercase().cmp(&b.title.to_lowercase()), true),
+                EventColumn::Weight => known(a.weight, b.weight),
+                EventColumn::Participants => known(a.participants, b.participants),
+            };
+            if sorted { sort.apply(ord) } else { ord }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pager_finish_and_exhaustion() {
+        let mut pager = Pager::new(5);
+        assert_eq!(pager.page, 0);
+        assert_eq!(pager.begin(), 1);
+        assert!(pager.loading);
+        pager.finish(1, 50);
+        assert_eq!((pager.page, pager.loading, pager.exhausted), (1, false, false));
+        assert_eq!(pager.begin(), 2);
+        // an empty page marks the end of the listing and keeps the last page
+        pager.finish(2, 0);
+        assert_eq!((pager.page, pager.loading, pager.exhausted), (1, false, true));
+        assert!(!pager.wants_next(0, 10));
+    }
+
+    #[test]
+    fn pager_prefetch_threshold() {
+        let mut pager = Pager::new(2);
+        assert!(pager.wants_next(10, 10));
+        assert!(!pager.wants_next(11, 10));
+        pager.begin();
+        // one page in flight at a time
+        assert!(!pager.wants_next(0, 10));
+        pager.finish(1, 50);
+        assert!(pager.wants_next(0, 10));
+        pager.begin();
+        pager.finish(2, 50);
+        // max_pages reached
+        assert!(!pager.wants_next(0, 10));
+    }
+
+    #[test]
+    fn pager_waits_after_a_failure() {
+        let mut pager = Pager::new(5);
+        pager.begin();
+        pager.fail();
+        assert!(!pager.loading);
+        assert!(!pager.wants_next(0, 10));
+        pager.retry_at = Some(Instant::now());
+        assert!(pager.wants_next(0, 10));
+        assert_eq!(pager.begin(), 1);
+        pager.finish(1, 50);
+        assert_eq!(pager.retry_at, None);
+    }
+
+    #[test]
+    fn remaining_rows_below_the_selection() {
+        let mut list = StatefulList::with_items(vec![1, 2, 3, 4]);
+        assert_eq!(list.remaining(), 3);
+        list.move_down();
+        list.move_down();
+        assert_eq!(list.remaining(), 2);
+        list.move_down();
+        list.move_down();
+        list.move_down();
+        assert_eq!(list.remaining(), 0);
+    }
+
+    #[test]
+    fn sort_cycles_directions_then_columns() {
+        let mut sort = Sort { column: TeamColumn::Rank, descending: false };
+        let mut seen = vec![];
+        for _ in 0..TeamColumn::ALL.len() * 2
+        {
+            seen.push((sort.column, sort.descending));
+            sort = sort.next(&TeamColumn::ALL);
+        }
+        let expected: Vec<_> = TeamColumn::ALL.iter().flat_map(|c| [(*c, false), (*c, true)]).collect();
+        assert_eq!(seen, expected);
+        // back to the first column ascending
+        assert_eq!(sort, Sort { column: TeamColumn::Rank, descending: false });
+    }
+
+    fn team(rank: u32, name: &str) -> TeamStat {
+        TeamStat {
+            rank: rank.to_string(),
+            name: name.to_string(),
+            points: String::new(),
+            country: String::new(),
+            country_code: None,
+            team_id: None,
+        }
+    }
+
+    fn movers(first: &HashMap<String, crate::store::Standing>, descending: bool) -> Vec<String> {
+        let sort = Sort { column: TeamColumn::Movers, descending };
+        let mut teams = vec![team(1, "a"), team(2, "b"), team(3, "c"), team(4, "d")];
+        teams.sort_by(|a, b| compare_teams(sort, first, a, b));
+        teams.into_iter().map(|t| t.name).collect()
+    }
+
+    #[test]
+    fn movers_without_a_first_snapshot_keep_rank_order() {
+        let first = HashMap::new();
+        assert_eq!(movers(&first, false), ["a", "b", "c", "d"]);
+        assert_eq!(movers(&first, true), ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn movers_missing_from_the_first_snapshot_go_last() {
+        use crate::store::Standing;
+        // a and c dropped one place, d climbed two, b is new
+        let first = HashMap::from([
+            ("a".to_string(), Standing { rank: 0, points: 0.0 }),
+            ("c".to_string(), Standing { rank: 2, points: 0.0 }),
+            ("d".to_string(), Standing { rank: 6, points: 0.0 }),
+        ]);
+        assert_eq!(movers(&first, false), ["a", "c", "d", "b"]);
+        assert_eq!(movers(&first, true), ["d", "a", "c", "b"]);
+    }
+
+    #[test]
+    fn academic_filter_cycle_and_unknown_teams() {
+        let filter = AcademicFilter::All;
+        assert_eq!(filter.next(), AcademicFilter::Academic);
+        assert_eq!(filter.next().next(), AcademicFilter::NonAcademic);
+        assert_eq!(filter.next().next().next(), AcademicFilter::All);
+        for (filter, shown) in [
+            (AcademicFilter::All, [true, true, true]),
+            (AcademicFilter::Academic, [true, false, false]),
+            (AcademicFilter::NonAcademic, [false, true, false]),
+        ]
+        {
+            assert_eq!([Some(true), Some(false), None].map(|a| filter.shows(a)), shown, "{:?}", filter);
+        }
     }
 }